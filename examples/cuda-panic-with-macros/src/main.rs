@@ -12,19 +12,16 @@ pub unsafe extern "ptx-kernel" fn example_kernel(a: i32, b: i32) {
     }
 }
 
+#[cfg(not(target_os = "cuda"))]
+ptx_support::prelude::include_kernels!(mod kernels = env!("KERNEL_PTX_PATH"));
+
 #[cfg(not(target_os = "cuda"))]
 fn main() {
     use cuda::driver;
     use cuda::driver::{Any, Block, Device, Grid};
-    use std::ffi::CString;
 
     driver::initialize().expect("Unable to initialize CUDA");
 
-    let ptx_assembly =
-        CString::new(include_str!(env!("KERNEL_PTX_PATH"))).expect("Unable to create sources");
-
-    let kernel_name = CString::new("example_kernel").expect("Unable to create kernel name string");
-
     let context = {
         Device(0)
             .expect("Unable to get CUDA device 0")
@@ -34,13 +31,13 @@ fn main() {
 
     let module = {
         context
-            .load_module(&ptx_assembly)
+            .load_module(kernels::PTX)
             .expect("Unable to create module")
     };
 
     let kernel = {
         module
-            .function(&kernel_name)
+            .function(kernels::EXAMPLE_KERNEL)
             .expect("Unable to find the kernel")
     };
 
@@ -2,11 +2,10 @@
 #![cfg_attr(target_os = "cuda", feature(abi_ptx, proc_macro_hygiene))]
 #![cfg_attr(target_os = "cuda", no_std)]
 
-#[no_mangle]
-#[cfg(target_os = "cuda")]
-pub unsafe extern "ptx-kernel" fn example_kernel(a: f64, b: f64) {
-    use ptx_support::prelude::*;
+use ptx_support::prelude::*;
 
+#[kernel(max_threads = 64)]
+pub fn example_kernel(a: f64, b: f64) {
     cuda_printf!(
         "Hello from block(%lu,%lu,%lu) and thread(%lu,%lu,%lu)\n",
         Context::block().index().x,
@@ -26,18 +25,62 @@ pub unsafe extern "ptx-kernel" fn example_kernel(a: f64, b: f64) {
     }
 }
 
+#[cfg(not(target_os = "cuda"))]
+struct CudaDriver<'a> {
+    module: &'a cuda::driver::Module<'a>,
+}
+
+#[cfg(not(target_os = "cuda"))]
+impl<'a> ptx_support::kernel::Driver for CudaDriver<'a> {
+    type Error = cuda::driver::Error;
+
+    fn launch_kernel(
+        &self,
+        name: &str,
+        config: &ptx_support::kernel::LaunchConfig,
+        args: &[ptx_support::kernel::LaunchArg],
+    ) -> Result<(), Self::Error> {
+        use cuda::driver::{Any, Block, Grid};
+        use std::ffi::CString;
+
+        assert_eq!(
+            config.shared_memory, 0,
+            "Dynamic shared memory is not supported"
+        );
+
+        let name = CString::new(name).expect("Unable to create kernel name string");
+
+        // The driver only needs the address of every argument value.
+        let args = args
+            .iter()
+            .map(|arg| Any(unsafe { &*(arg.as_ptr() as *const u8) }))
+            .collect::<Vec<_>>();
+
+        self.module.function(&name)?.launch(
+            &args,
+            Grid::xyz(config.grid.x, config.grid.y, config.grid.z),
+            Block::xyz(config.block.x, config.block.y, config.block.z),
+        )
+    }
+}
+
 #[cfg(not(target_os = "cuda"))]
 fn main() {
     use cuda::driver;
-    use cuda::driver::{Any, Block, Device, Grid};
+    use cuda::driver::Device;
+    use ptx_support_build::{apply_launch_bounds, Manifest};
     use std::ffi::CString;
 
     driver::initialize().expect("Unable to initialize CUDA");
 
-    let ptx_assembly =
-        CString::new(include_str!(env!("KERNEL_PTX_PATH"))).expect("Unable to create sources");
+    let ptx_source = apply_launch_bounds(include_str!(env!("KERNEL_PTX_PATH")))
+        .expect("Unable to apply launch bounds");
 
-    let kernel_name = CString::new("example_kernel").expect("Unable to create kernel name string");
+    Manifest::from_ptx(&ptx_source)
+        .and_then(|manifest| manifest.check_kernel::<ExampleKernel>())
+        .expect("Kernel signature doesn't match the PTX assembly");
+
+    let ptx_assembly = CString::new(ptx_source).expect("Unable to create sources");
 
     let context = {
         Device(0)
@@ -52,15 +95,7 @@ fn main() {
             .expect("Unable to create module")
     };
 
-    let kernel = {
-        module
-            .function(&kernel_name)
-            .expect("Unable to find the kernel")
-    };
-
     println!("You should now see messages right from the kernel:");
 
-    kernel
-        .launch(&[Any(&11.63), Any(&15.36)], Grid::x(2), Block::x(8))
-        .unwrap_err();
+    ExampleKernel::launch(&CudaDriver { module: &module }, 2, 8, 11.63, 15.36).unwrap_err();
 }
@@ -0,0 +1,148 @@
+use crate::error::ManifestError;
+use crate::manifest::Manifest;
+
+/// Adds `.maxntid`, `.minnctapersm` and `.reqntid` directives to entry points,
+/// according to launch bounds recorded in the manifest.
+///
+/// LLVM doesn't emit the directives for Rust kernels, so the PTX assembly has to be
+/// processed before loading it. Directives which are already present are kept,
+/// when their operands are the same as in the launch bounds.
+pub fn apply_launch_bounds(source: &str) -> Result<String, ManifestError> {
+    let manifest = Manifest::from_ptx(source)?;
+    let mut result = source.to_owned();
+
+    for record in &manifest.bounds {
+        let body_start = find_entry_body(&result, &record.kernel).ok_or_else(|| {
+            ManifestError::MissingKernel {
+                name: record.kernel.clone(),
+            }
+        })?;
+
+        let header = &result[..body_start];
+        let header = &header[header.rfind(')').unwrap_or(0)..];
+
+        let mut directives = String::new();
+
+        for (directive, operands) in &record.directives {
+            match find_directive(header, directive) {
+                None => directives += &format!(".{} {}\n", directive, operands),
+
+                Some(existing) if same_operands(existing, operands) => {}
+
+                Some(existing) => {
+                    return Err(ManifestError::ConflictingLaunchBounds {
+                        kernel: record.kernel.clone(),
+                        directive: directive.clone(),
+                        existing: existing.to_owned(),
+                        required: operands.clone(),
+                    });
+                }
+            }
+        }
+
+        result.insert_str(body_start, &directives);
+    }
+
+    Ok(result)
+}
+
+/// Operands of a directive in the header of an entry point, e.g. `256, 1, 1` for `.maxntid`.
+fn find_directive<'a>(header: &'a str, directive: &str) -> Option<&'a str> {
+    let pattern = format!(".{} ", directive);
+    let start = header.find(&pattern)? + pattern.len();
+    let operands = &header[start..];
+    let end = operands.find(['\n', '{']).unwrap_or(operands.len());
+
+    Some(operands[..end].trim())
+}
+
+/// Compares operands ignoring whitespace, where missing dimensions are `1`.
+fn same_operands(left: &str, right: &str) -> bool {
+    let operands = |operands: &str| {
+        let mut operands = operands
+            .split(',')
+            .map(|operand| operand.trim().to_owned())
+            .collect::<Vec<_>>();
+
+        while operands.len() > 1 && operands.last().is_some_and(|operand| operand == "1") {
+            operands.pop();
+        }
+
+        operands
+    };
+
+    operands(left) == operands(right)
+}
+
+/// Position of the opening brace of the entry point body:
+/// `.visible .entry name(\n\t.param ...\n)\n{`.
+fn find_entry_body(source: &str, name: &str) -> Option<usize> {
+    let entry = source.find(&format!(".entry {}(", name))?;
+    let params_end = entry + source[entry..].find(')')?;
+
+    Some(params_end + source[params_end..].find('{')?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::tests::declaration;
+
+    fn bounds_ptx(record: &str) -> String {
+        let mut source = String::from(".version 6.0\n.target sm_30\n.address_size 64\n\n");
+
+        source += &declaration("__ptx_support_manifest_bounds_blur", record);
+        source += ".visible .entry blur(\n\t.param .u64 blur_param_0\n)\n{\n\tret;\n}\n";
+        source += ".visible .entry blur_rows(\n\t.param .u64 blur_rows_param_0\n)\n{\n\tret;\n}\n";
+        source
+    }
+
+    #[test]
+    fn test_apply_launch_bounds() {
+        let source = bounds_ptx("bounds\tblur\tmaxntid\t256, 1, 1\tminnctapersm\t2\n");
+        let processed = apply_launch_bounds(&source).unwrap();
+
+        assert!(processed.contains(
+            ".entry blur(\n\t.param .u64 blur_param_0\n)\n.maxntid 256, 1, 1\n.minnctapersm 2\n{"
+        ));
+
+        assert!(processed.contains(".entry blur_rows(\n\t.param .u64 blur_rows_param_0\n)\n{"));
+        assert_eq!(apply_launch_bounds(&processed), Ok(processed));
+    }
+
+    #[test]
+    fn test_existing_directives() {
+        let source = bounds_ptx("bounds\tblur\tmaxntid\t256, 1, 1\n");
+
+        let matching = source.replace("blur_param_0\n)\n", "blur_param_0\n)\n.maxntid 256\n");
+        assert_eq!(apply_launch_bounds(&matching), Ok(matching.clone()));
+
+        let conflicting =
+            source.replace("blur_param_0\n)\n", "blur_param_0\n)\n.maxntid 128, 1, 1\n");
+
+        assert_eq!(
+            apply_launch_bounds(&conflicting),
+            Err(ManifestError::ConflictingLaunchBounds {
+                kernel: "blur".into(),
+                directive: "maxntid".into(),
+                existing: "128, 1, 1".into(),
+                required: "256, 1, 1".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_missing_entry() {
+        let source = declaration(
+            "__ptx_support_manifest_bounds_blur",
+            "bounds\tblur\treqntid\t32, 8, 1\n",
+        );
+
+        assert_eq!(
+            apply_launch_bounds(&source),
+            Err(ManifestError::MissingKernel {
+                name: "blur".into()
+            })
+        );
+    }
+}
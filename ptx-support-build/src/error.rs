@@ -0,0 +1,56 @@
+use failure::Fail;
+
+#[derive(Debug, Fail, PartialEq)]
+pub enum ManifestError {
+    #[fail(display = "Unable to read PTX assembly: {}", reason)]
+    Io { reason: String },
+
+    #[fail(display = "Malformed manifest record {:?}", symbol)]
+    MalformedRecord { symbol: String },
+
+    #[fail(display = "Unknown manifest record kind {:?}", kind)]
+    UnknownKind { kind: String },
+
+    #[fail(display = "Kernel {:?} is not found in the PTX assembly", name)]
+    MissingKernel { name: String },
+
+    #[fail(display = "Struct {:?} is not found in the PTX assembly", name)]
+    MissingStruct { name: String },
+
+    #[fail(
+        display = "Struct {:?} is recorded more than once in the PTX assembly",
+        name
+    )]
+    DuplicateStruct { name: String },
+
+    #[fail(
+        display = "Kernel {:?} signature mismatch: the device has {}, the host has {}",
+        name, device, host
+    )]
+    SignatureMismatch {
+        name: String,
+        device: String,
+        host: String,
+    },
+
+    #[fail(
+        display = "Struct {:?} layout mismatch: the device has {}, the host has {}",
+        name, device, host
+    )]
+    LayoutMismatch {
+        name: String,
+        device: String,
+        host: String,
+    },
+
+    #[fail(
+        display = "Kernel {:?} already has `.{} {}`, but launch bounds require `.{} {}`",
+        kernel, directive, existing, directive, required
+    )]
+    ConflictingLaunchBounds {
+        kernel: String,
+        directive: String,
+        existing: String,
+        required: String,
+    },
+}
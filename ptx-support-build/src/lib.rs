@@ -0,0 +1,18 @@
+#![deny(warnings)]
+
+//! Host-side tools for PTX assemblies built with `ptx-support`.
+//!
+//! Device macros embed manifest records (see `ptx_support::manifest`) into the assembly.
+//! This crate reads them back, so build scripts and launchers can check that the host
+//! and the device agree on kernel signatures and parameter layouts, and apply launch bounds
+//! of kernels to the assembly.
+
+mod bounds;
+mod error;
+mod manifest;
+
+pub use crate::bounds::apply_launch_bounds;
+pub use crate::error::ManifestError;
+pub use crate::manifest::{
+    BoundsRecord, ConstantRecord, KernelRecord, Manifest, ParamRecord, StructRecord,
+};
@@ -0,0 +1,602 @@
+use std::fs;
+use std::path::Path;
+
+use ptx_support::kernel::{Kernel, KernelParam, ParamSignature};
+use ptx_support::manifest::SYMBOL_PREFIX;
+
+use crate::error::ManifestError;
+
+/// Records found in a PTX assembly.
+#[derive(Debug, Default, PartialEq)]
+pub struct Manifest {
+    pub kernels: Vec<KernelRecord>,
+    pub constants: Vec<ConstantRecord>,
+    pub structs: Vec<StructRecord>,
+    pub bounds: Vec<BoundsRecord>,
+}
+
+/// Kernel entry point declared with `#[kernel]`.
+#[derive(Debug, PartialEq)]
+pub struct KernelRecord {
+    pub name: String,
+    pub params: Vec<ParamRecord>,
+}
+
+/// Parameter of a kernel entry point, as seen by the device.
+#[derive(Debug, PartialEq)]
+pub struct ParamRecord {
+    pub name: String,
+    pub ty: String,
+    pub size: usize,
+    pub align: usize,
+}
+
+/// Launch bounds declared with `#[kernel(...)]`.
+#[derive(Debug, PartialEq)]
+pub struct BoundsRecord {
+    pub kernel: String,
+
+    /// PTX directives with their operands, e.g. `("maxntid", "256, 1, 1")`.
+    pub directives: Vec<(String, String)>,
+}
+
+/// Constant memory variable declared with `#[constant]`.
+#[derive(Debug, PartialEq)]
+pub struct ConstantRecord {
+    pub name: String,
+
+    /// Symbol in the PTX assembly, qualified with the crate name and a hash of the declaration site.
+    pub symbol: String,
+    pub ty: String,
+    pub size: usize,
+    pub align: usize,
+}
+
+/// Struct deriving `KernelParam`, as laid out by the device.
+#[derive(Debug, PartialEq)]
+pub struct StructRecord {
+    pub name: String,
+
+    /// Module path of the struct, to tell apart structs of different modules.
+    pub path: String,
+    pub size: usize,
+    pub align: usize,
+    pub field_offsets: Vec<(String, usize)>,
+}
+
+impl Manifest {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ManifestError> {
+        let source = fs::read_to_string(path).map_err(|error| ManifestError::Io {
+            reason: error.to_string(),
+        })?;
+
+        Manifest::from_ptx(&source)
+    }
+
+    pub fn from_ptx(source: &str) -> Result<Self, ManifestError> {
+        let mut manifest = Manifest::default();
+
+        for (symbol, bytes) in find_records(source) {
+            let malformed = || ManifestError::MalformedRecord {
+                symbol: symbol.to_owned(),
+            };
+
+            let record = bytes.ok_or_else(malformed)?;
+            let record = String::from_utf8(record).map_err(|_| malformed())?;
+            let mut fields = record.trim_end_matches('\n').split('\t');
+
+            match fields.next() {
+                Some("kernel") => manifest
+                    .kernels
+                    .push(parse_kernel(&mut fields).ok_or_else(malformed)?),
+
+                Some("constant") => manifest
+                    .constants
+                    .push(parse_constant(&mut fields).ok_or_else(malformed)?),
+
+                Some("param") => manifest
+                    .structs
+                    .push(parse_struct(&mut fields).ok_or_else(malformed)?),
+
+                Some("bounds") => manifest
+                    .bounds
+                    .push(parse_bounds(&mut fields).ok_or_else(malformed)?),
+
+                Some(kind) => {
+                    return Err(ManifestError::UnknownKind {
+                        kind: kind.to_owned(),
+                    });
+                }
+
+                None => return Err(malformed()),
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    pub fn kernel(&self, name: &str) -> Option<&KernelRecord> {
+        self.kernels.iter().find(|kernel| kernel.name == name)
+    }
+
+    pub fn launch_bounds(&self, kernel: &str) -> Option<&BoundsRecord> {
+        self.bounds.iter().find(|record| record.kernel == kernel)
+    }
+
+    /// First constant named `name`, see `constant_symbol()` for constants of different modules
+    /// with the same name.
+    pub fn constant(&self, name: &str) -> Option<&ConstantRecord> {
+        self.constants.iter().find(|constant| constant.name == name)
+    }
+
+    /// Constant with the PTX symbol `symbol`, as known to the host by `ConstantSymbol::name()`.
+    pub fn constant_symbol(&self, symbol: &str) -> Option<&ConstantRecord> {
+        self.constants
+            .iter()
+            .find(|constant| constant.symbol == symbol)
+    }
+
+    pub fn struct_layout(&self, name: &str) -> Option<&StructRecord> {
+        self.structs.iter().find(|record| record.name == name)
+    }
+
+    /// Checks that the device kernel has the same signature as the host declaration.
+    pub fn check_kernel<K: Kernel>(&self) -> Result<(), ManifestError> {
+        let record = self
+            .kernel(K::NAME)
+            .ok_or_else(|| ManifestError::MissingKernel {
+                name: K::NAME.to_owned(),
+            })?;
+
+        let device = record
+            .params
+            .iter()
+            .map(|param| {
+                (
+                    param.name.as_str(),
+                    param.ty.as_str(),
+                    param.size,
+                    param.align,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let host = K::PARAMS
+            .iter()
+            .map(|param: &ParamSignature| {
+                (param.name, param.ty, param.layout.size, param.layout.align)
+            })
+            .collect::<Vec<_>>();
+
+        if device != host {
+            return Err(ManifestError::SignatureMismatch {
+                name: K::NAME.to_owned(),
+                device: format_signature(&device),
+                host: format_signature(&host),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the device lays out a struct parameter the same way as the host.
+    ///
+    /// Structs are looked up by their name and module path, which have to be unique.
+    pub fn check_struct<T: KernelParam>(&self) -> Result<(), ManifestError> {
+        let layout = T::LAYOUT;
+        let name = format!("{}::{}", layout.path, layout.name);
+
+        let mut records = self
+            .structs
+            .iter()
+            .filter(|record| record.name == layout.name && record.path == layout.path);
+
+        let record = match (records.next(), records.next()) {
+            (Some(record), None) => record,
+            (Some(_), Some(_)) => return Err(ManifestError::DuplicateStruct { name }),
+            (None, _) => return Err(ManifestError::MissingStruct { name }),
+        };
+
+        let host = StructRecord {
+            name: layout.name.to_owned(),
+            path: layout.path.to_owned(),
+            size: layout.size,
+            align: layout.align,
+            field_offsets: layout
+                .fields
+                .iter()
+                .map(|field| (field.name.to_owned(), field.offset))
+                .collect(),
+        };
+
+        if *record != host {
+            return Err(ManifestError::LayoutMismatch {
+                name,
+                device: record.describe(),
+                host: host.describe(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl StructRecord {
+    fn describe(&self) -> String {
+        let fields = self
+            .field_offsets
+            .iter()
+            .map(|(name, offset)| format!("{} at {}", name, offset))
+            .collect::<Vec<_>>();
+
+        format!(
+            "size {}, align {} ({})",
+            self.size,
+            self.align,
+            fields.join(", ")
+        )
+    }
+}
+
+fn format_signature(params: &[(&str, &str, usize, usize)]) -> String {
+    let params = params
+        .iter()
+        .map(|(name, ty, size, align)| format!("{}: {} (size {}, align {})", name, ty, size, align))
+        .collect::<Vec<_>>();
+
+    format!("({})", params.join(", "))
+}
+
+/// Finds initialized global byte arrays named with the manifest prefix:
+/// `.global .align 1 .b8 __ptx_support_manifest_...[N] = {107, 101, ...};`
+fn find_records(source: &str) -> Vec<(&str, Option<Vec<u8>>)> {
+    let mut records = vec![];
+    let mut rest = source;
+
+    while let Some(start) = rest.find(SYMBOL_PREFIX) {
+        let tail = &rest[start..];
+        let symbol_len = tail
+            .find(|chr: char| !(chr.is_ascii_alphanumeric() || chr == '_' || chr == '$'))
+            .unwrap_or(tail.len());
+
+        let symbol = &tail[..symbol_len];
+        let after_symbol = &tail[symbol_len..];
+
+        rest = after_symbol;
+
+        // Only declarations have an array size right after the name.
+        if !after_symbol.starts_with('[') {
+            continue;
+        }
+
+        let bytes = after_symbol
+            .find(';')
+            .map(|end| &after_symbol[..end])
+            .and_then(parse_initializer);
+
+        records.push((symbol, bytes));
+    }
+
+    records
+}
+
+fn parse_initializer(declaration: &str) -> Option<Vec<u8>> {
+    let start = declaration.find('{')? + 1;
+    let end = declaration.rfind('}')?;
+
+    declaration[start..end]
+        .split(',')
+        .map(|byte| byte.trim().parse::<u8>().ok())
+        .collect()
+}
+
+fn parse_num<'a, I: Iterator<Item = &'a str>>(fields: &mut I) -> Option<usize> {
+    fields.next()?.parse().ok()
+}
+
+fn parse_kernel<'a, I: Iterator<Item = &'a str>>(fields: &mut I) -> Option<KernelRecord> {
+    let name = fields.next()?.to_owned();
+    let count = parse_num(fields)?;
+
+    let params = (0..count)
+        .map(|_| {
+            Some(ParamRecord {
+                name: fields.next()?.to_owned(),
+                ty: fields.next()?.to_owned(),
+                size: parse_num(fields)?,
+                align: parse_num(fields)?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    match fields.next() {
+        None => Some(KernelRecord { name, params }),
+        Some(_) => None,
+    }
+}
+
+fn parse_constant<'a, I: Iterator<Item = &'a str>>(fields: &mut I) -> Option<ConstantRecord> {
+    let record = ConstantRecord {
+        name: fields.next()?.to_owned(),
+        symbol: fields.next()?.to_owned(),
+        ty: fields.next()?.to_owned(),
+        size: parse_num(fields)?,
+        align: parse_num(fields)?,
+    };
+
+    match fields.next() {
+        None => Some(record),
+        Some(_) => None,
+    }
+}
+
+fn parse_struct<'a, I: Iterator<Item = &'a str>>(fields: &mut I) -> Option<StructRecord> {
+    let name = fields.next()?.to_owned();
+    let path = fields.next()?.to_owned();
+    let size = parse_num(fields)?;
+    let align = parse_num(fields)?;
+    let mut field_offsets = vec![];
+
+    while let Some(field) = fields.next() {
+        field_offsets.push((field.to_owned(), parse_num(fields)?));
+    }
+
+    Some(StructRecord {
+        name,
+        path,
+        size,
+        align,
+        field_offsets,
+    })
+}
+
+fn parse_bounds<'a, I: Iterator<Item = &'a str>>(fields: &mut I) -> Option<BoundsRecord> {
+    let kernel = fields.next()?.to_owned();
+    let mut directives = vec![];
+
+    while let Some(directive) = fields.next() {
+        directives.push((directive.to_owned(), fields.next()?.to_owned()));
+    }
+
+    Some(BoundsRecord { kernel, directives })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use ptx_support::kernel::ParamLayout;
+
+    use super::*;
+
+    pub(crate) fn declaration(symbol: &str, record: &str) -> String {
+        let bytes = record
+            .bytes()
+            .map(|byte| byte.to_string())
+            .collect::<Vec<_>>();
+
+        format!(
+            ".visible .global .align 1 .b8 {}[{}] = {{{}}};\n",
+            symbol,
+            bytes.len(),
+            bytes.join(", ")
+        )
+    }
+
+    struct ExampleKernel;
+
+    impl Kernel for ExampleKernel {
+        const NAME: &'static str = "example_kernel";
+        const PARAMS: &'static [ParamSignature] = &[
+            ParamSignature {
+                name: "a",
+                ty: "f64",
+                layout: ParamLayout::of::<f64>("f64"),
+            },
+            ParamSignature {
+                name: "output",
+                ty: "DeviceSliceMut < f64 >",
+                layout: ParamLayout::of::<[u64; 2]>("DeviceSliceMut"),
+            },
+        ];
+    }
+
+    fn example_ptx() -> String {
+        let mut source = String::from(".version 6.0\n.target sm_30\n.address_size 64\n\n");
+
+        source += &declaration(
+            "__ptx_support_manifest_kernel_example_kernel",
+            "kernel\texample_kernel\t2\ta\tf64\t8\t8\toutput\tDeviceSliceMut < f64 >\t16\t8\n",
+        );
+
+        source += &declaration(
+            "__ptx_support_manifest_constant_kernels_WEIGHTS_5e1a0c3b9d2f4e67",
+            "constant\tWEIGHTS\t__ptx_support_constant_kernels_WEIGHTS_5e1a0c3b9d2f4e67\t[f32 ; 4]\t16\t4\n",
+        );
+
+        source += &declaration(
+            "__ptx_support_manifest_param_kernels_Params_0b4c6f2e8a1d3957",
+            "param\tParams\tkernels::blur\t16\t8\tscale\t0\toffset\t8\n",
+        );
+
+        source += ".visible .entry example_kernel(\n\t.param .f64 example_kernel_param_0\n)\n{\n\tret;\n}\n";
+        source
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = Manifest::from_ptx(&example_ptx()).unwrap();
+
+        assert_eq!(
+            manifest.kernel("example_kernel"),
+            Some(&KernelRecord {
+                name: "example_kernel".into(),
+                params: vec![
+                    ParamRecord {
+                        name: "a".into(),
+                        ty: "f64".into(),
+                        size: 8,
+                        align: 8,
+                    },
+                    ParamRecord {
+                        name: "output".into(),
+                        ty: "DeviceSliceMut < f64 >".into(),
+                        size: 16,
+                        align: 8,
+                    },
+                ],
+            })
+        );
+
+        assert_eq!(
+            manifest.constant("WEIGHTS"),
+            Some(&ConstantRecord {
+                name: "WEIGHTS".into(),
+                symbol: "__ptx_support_constant_kernels_WEIGHTS_5e1a0c3b9d2f4e67".into(),
+                ty: "[f32 ; 4]".into(),
+                size: 16,
+                align: 4,
+            })
+        );
+
+        assert_eq!(
+            manifest.constant_symbol("__ptx_support_constant_kernels_WEIGHTS_5e1a0c3b9d2f4e67"),
+            manifest.constant("WEIGHTS")
+        );
+
+        assert_eq!(
+            manifest
+                .struct_layout("Params")
+                .map(|record| &record.field_offsets),
+            Some(&vec![("scale".into(), 0), ("offset".into(), 8)])
+        );
+    }
+
+    #[test]
+    fn test_malformed_record() {
+        let source = declaration(
+            "__ptx_support_manifest_kernel_broken",
+            "kernel\tbroken\t3\n",
+        );
+
+        assert_eq!(
+            Manifest::from_ptx(&source),
+            Err(ManifestError::MalformedRecord {
+                symbol: "__ptx_support_manifest_kernel_broken".into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_kernel() {
+        let manifest = Manifest::from_ptx(&example_ptx()).unwrap();
+
+        assert_eq!(manifest.check_kernel::<ExampleKernel>(), Ok(()));
+
+        let a = "a: f64 (size 8, align 8)";
+        let output = "output: DeviceSliceMut < f64 > (size 16, align 8)";
+
+        let mut mismatched = manifest;
+        mismatched.kernels[0].params.pop();
+
+        assert_eq!(
+            mismatched.check_kernel::<ExampleKernel>(),
+            Err(ManifestError::SignatureMismatch {
+                name: "example_kernel".into(),
+                device: format!("({})", a),
+                host: format!("({}, {})", a, output),
+            })
+        );
+
+        let mut mismatched = Manifest::from_ptx(&example_ptx()).unwrap();
+        mismatched.kernels[0].params[1].align = 16;
+
+        assert_eq!(
+            mismatched.check_kernel::<ExampleKernel>(),
+            Err(ManifestError::SignatureMismatch {
+                name: "example_kernel".into(),
+                device: format!("({}, {})", a, output.replace("align 8", "align 16")),
+                host: format!("({}, {})", a, output),
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_struct() {
+        #[derive(Clone, Copy)]
+        #[repr(C)]
+        struct Params {
+            scale: f32,
+            offset: f64,
+        }
+
+        unsafe impl KernelParam for Params {
+            const LAYOUT: ParamLayout = ParamLayout {
+                name: "Params",
+                path: "kernels::conv",
+                size: 16,
+                align: 8,
+                fields: &[
+                    ptx_support::kernel::FieldLayout {
+                        name: "scale",
+                        ty: "f32",
+                        offset: 0,
+                        size: 4,
+                        align: 4,
+                    },
+                    ptx_support::kernel::FieldLayout {
+                        name: "offset",
+                        ty: "f64",
+                        offset: 4,
+                        size: 8,
+                        align: 8,
+                    },
+                ],
+            };
+        }
+
+        let manifest = Manifest::from_ptx(&example_ptx()).unwrap();
+
+        assert_eq!(
+            manifest.check_struct::<Params>(),
+            Err(ManifestError::MissingStruct {
+                name: "kernels::conv::Params".into(),
+            })
+        );
+
+        let conv_params = |offset| {
+            declaration(
+                "__ptx_support_manifest_param_kernels_Params_d27e05a4c9b1f638",
+                &format!(
+                    "param\tParams\tkernels::conv\t16\t8\tscale\t0\toffset\t{}\n",
+                    offset
+                ),
+            )
+        };
+
+        let manifest = Manifest::from_ptx(&(example_ptx() + &conv_params(8))).unwrap();
+
+        assert_eq!(
+            manifest.check_struct::<Params>(),
+            Err(ManifestError::LayoutMismatch {
+                name: "kernels::conv::Params".into(),
+                device: "size 16, align 8 (scale at 0, offset at 8)".into(),
+                host: "size 16, align 8 (scale at 0, offset at 4)".into(),
+            })
+        );
+
+        let manifest = Manifest::from_ptx(&(example_ptx() + &conv_params(4))).unwrap();
+
+        assert_eq!(manifest.structs[1].path, "kernels::conv");
+        assert_eq!(manifest.check_struct::<Params>(), Ok(()));
+
+        let source = example_ptx() + &conv_params(4) + &conv_params(4);
+
+        assert_eq!(
+            Manifest::from_ptx(&source)
+                .unwrap()
+                .check_struct::<Params>(),
+            Err(ManifestError::DuplicateStruct {
+                name: "kernels::conv::Params".into(),
+            })
+        );
+    }
+}
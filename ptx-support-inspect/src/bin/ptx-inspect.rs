@@ -0,0 +1,244 @@
+use std::env;
+use std::process;
+
+use ptx_support_inspect::{
+    lint, occupancy, occupancy_table, ArchLimits, KernelResources, LintConfig, Module, ARCH_LIMITS,
+};
+
+const USAGE: &str = "\
+Usage:
+    ptx-inspect lint [OPTIONS] FILE...
+    ptx-inspect occupancy [OPTIONS] FILE
+
+Lint options:
+    --debug                 kernels are built with a debug feature (allows `vprintf`)
+    --alloc                 kernels are built with the `alloc` feature (allows `malloc`)
+    --allow-local-memory    allows local memory frames
+    --allow-f64             allows double precision arithmetic
+    --max-target sm_XX      newest allowed target architecture
+
+Occupancy options:
+    --target sm_XX          target architecture (default: the target of the assembly)
+    --kernel NAME           only the kernel (default: all kernels)
+    --block-size N          only the block size (default: all multiples of a warp)
+    --registers N           registers per thread reported by `ptxas` (default: rough estimate
+                            from PTX virtual registers)
+    --dynamic-shared BYTES  shared memory requested at launch";
+
+/// Exit code when the checked assemblies have problems.
+const EXIT_FAILED: i32 = 1;
+
+/// Exit code for invalid arguments or unreadable assemblies.
+const EXIT_ERROR: i32 = 2;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let code = match args.split_first() {
+        Some((command, args)) if command == "lint" => run_lint(args),
+        Some((command, args)) if command == "occupancy" => run_occupancy(args),
+
+        Some((command, _)) if command == "--help" || command == "-h" => {
+            println!("{}", USAGE);
+            0
+        }
+
+        _ => usage_error("expected a command"),
+    };
+
+    process::exit(code);
+}
+
+fn run_lint(args: &[String]) -> i32 {
+    let mut config = LintConfig::default();
+    let mut files = vec![];
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--debug" => config.debug = true,
+            "--alloc" => config.alloc = true,
+            "--allow-local-memory" => config.allow_local_memory = true,
+            "--allow-f64" => config.allow_f64 = true,
+
+            "--max-target" => {
+                let target = args
+                    .next()
+                    .and_then(|value| value.trim_start_matches("sm_").parse().ok());
+
+                match target {
+                    Some(target) => config.max_target = Some(target),
+                    None => return usage_error("`--max-target` expects a value like `sm_35`"),
+                }
+            }
+
+            option if option.starts_with("--") => {
+                return usage_error(&format!("unknown option `{}`", option));
+            }
+
+            file => files.push(file),
+        }
+    }
+
+    if files.is_empty() {
+        return usage_error("expected at least one PTX file");
+    }
+
+    let mut code = 0;
+
+    for file in files {
+        let module = match Module::from_file(file) {
+            Ok(module) => module,
+            Err(error) => {
+                eprintln!("{}: {}", file, error);
+                return EXIT_ERROR;
+            }
+        };
+
+        for lint in lint(&module, &config) {
+            println!("{}: {}", file, lint);
+            code = EXIT_FAILED;
+        }
+    }
+
+    code
+}
+
+fn run_occupancy(args: &[String]) -> i32 {
+    let mut target = None;
+    let mut kernel = None;
+    let mut block_size = None;
+    let mut registers = None;
+    let mut dynamic_shared_memory = 0;
+    let mut file = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut number = || args.next().and_then(|value| value.parse::<u32>().ok());
+
+        match &arg[..] {
+            "--target" => {
+                target = args
+                    .next()
+                    .and_then(|value| value.trim_start_matches("sm_").parse().ok());
+
+                if target.is_none() {
+                    return usage_error("`--target` expects a value like `sm_61`");
+                }
+            }
+
+            "--block-size" | "--registers" | "--dynamic-shared" => match (&arg[..], number()) {
+                ("--block-size", Some(value)) => block_size = Some(value),
+                ("--registers", Some(value)) => registers = Some(value),
+                (_, Some(value)) => dynamic_shared_memory = value,
+                (option, None) => return usage_error(&format!("`{}` expects a number", option)),
+            },
+
+            "--kernel" => {
+                kernel = args.next();
+
+                if kernel.is_none() {
+                    return usage_error("`--kernel` expects a name");
+                }
+            }
+
+            option if option.starts_with("--") => {
+                return usage_error(&format!("unknown option `{}`", option));
+            }
+
+            path if file.is_none() => file = Some(path),
+            _ => return usage_error("expected a single PTX file"),
+        }
+    }
+
+    let file = match file {
+        Some(file) => file,
+        None => return usage_error("expected a PTX file"),
+    };
+
+    let module = match Module::from_file(file) {
+        Ok(module) => module,
+        Err(error) => {
+            eprintln!("{}: {}", file, error);
+            return EXIT_ERROR;
+        }
+    };
+
+    let limits = match target
+        .or_else(|| module.sm_version())
+        .and_then(ArchLimits::find)
+    {
+        Some(limits) => limits,
+        None => {
+            let supported: Vec<_> = ARCH_LIMITS
+                .iter()
+                .map(|limits| format!("sm_{}", limits.sm))
+                .collect();
+
+            eprintln!(
+                "{}: unknown target architecture, supported are: {}",
+                file,
+                supported.join(", ")
+            );
+            return EXIT_ERROR;
+        }
+    };
+
+    let entries: Vec<_> = module
+        .entries()
+        .filter(|entry| kernel.map_or(true, |name| entry.name == *name))
+        .collect();
+
+    if entries.is_empty() {
+        eprintln!("{}: no matching kernels", file);
+        return EXIT_ERROR;
+    }
+
+    for entry in entries {
+        let mut resources = KernelResources::from_function(&module, entry, limits);
+
+        resources.dynamic_shared_memory = dynamic_shared_memory;
+        resources.registers_per_thread = registers.unwrap_or(resources.registers_per_thread);
+
+        let estimated = match registers {
+            Some(_) => "",
+            None => "estimated ",
+        };
+
+        println!(
+            "{} (sm_{}, {}{} registers, {} bytes of shared memory)",
+            entry.name,
+            limits.sm,
+            estimated,
+            resources.registers_per_thread,
+            resources.shared_memory + resources.dynamic_shared_memory
+        );
+
+        println!("  block size  blocks  warps  occupancy  limited by");
+
+        let table = match block_size {
+            Some(block_size) => vec![occupancy(limits, &resources, block_size)],
+            None => occupancy_table(limits, &resources),
+        };
+
+        for row in table {
+            println!(
+                "  {:>10}  {:>6}  {:>5}  {:>8.0}%  {}",
+                row.block_size,
+                row.active_blocks,
+                row.active_warps,
+                row.occupancy * 100.0,
+                row.limiter
+            );
+        }
+
+        println!();
+    }
+
+    0
+}
+
+fn usage_error(message: &str) -> i32 {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    EXIT_ERROR
+}
@@ -0,0 +1,10 @@
+use failure::Fail;
+
+#[derive(Debug, Fail, PartialEq)]
+pub enum InspectError {
+    #[fail(display = "Unable to read PTX assembly: {}", reason)]
+    Io { reason: String },
+
+    #[fail(display = "Unable to parse PTX assembly at line {}: {}", line, reason)]
+    Parse { line: usize, reason: String },
+}
@@ -0,0 +1,27 @@
+#![deny(warnings)]
+
+//! Model of PTX assemblies, for inspecting the code generated for kernels.
+//!
+//! ```ignore
+//! let module = Module::from_file(env!("KERNEL_PTX_PATH"))?;
+//! let kernel = module.entry("example_kernel").unwrap();
+//!
+//! assert!(!kernel.uses_type(".f64"));
+//! assert!(!kernel.calls_function("vprintf"));
+//! ```
+
+mod error;
+mod lint;
+mod module;
+mod occupancy;
+mod parser;
+
+pub use crate::error::InspectError;
+pub use crate::lint::{lint, Lint, LintConfig, LintKind, SourceLocation};
+pub use crate::module::{
+    Function, FunctionKind, Instruction, Loc, Module, Param, RegisterDecl, SourceFile, StateSpace,
+    Variable, Version,
+};
+pub use crate::occupancy::{
+    occupancy, occupancy_table, ArchLimits, KernelResources, Limiter, Occupancy, ARCH_LIMITS,
+};
@@ -0,0 +1,317 @@
+use std::fmt;
+
+use crate::module::{Function, Instruction, Module, StateSpace};
+
+/// Arithmetic and special function opcodes, which are slow in double precision on consumer GPUs.
+/// Loads, stores, moves and conversions of `f64` values run at full speed.
+const F64_ARITHMETIC: &[&str] = &[
+    "add", "sub", "mul", "mad", "fma", "div", "rcp", "sqrt", "rsqrt", "min", "max", "abs", "neg",
+    "sin", "cos", "lg2", "ex2", "tanh", "setp",
+];
+
+/// What is allowed in the checked assembly.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LintConfig {
+    /// Kernels are built with a debug feature, so `cuda_printf!` calls are expected.
+    ///
+    /// The panic handler of `ptx-support` reports panics with `cuda_printf!` when the `macros`
+    /// feature is enabled, so its `vprintf` calls are allowed in release builds too.
+    pub debug: bool,
+
+    /// Kernels are built with the `alloc` feature.
+    pub alloc: bool,
+
+    pub allow_local_memory: bool,
+
+    /// Kernels target GPUs with fast double precision (e.g. Tesla series).
+    ///
+    /// This is not derived from the SM version, which doesn't tell apart consumer
+    /// and data center GPUs: `sm_35` is both the GeForce GTX 780 and the Tesla K40.
+    pub allow_f64: bool,
+
+    /// Newest allowed SM version, e.g. `35` for `sm_35`.
+    pub max_target: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    Printing,
+    Malloc,
+    LocalMemory,
+    DoublePrecision,
+    TargetTooNew,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Lint {
+    pub kind: LintKind,
+    pub message: String,
+
+    /// Function containing the problem, if it's not module-wide.
+    pub function: Option<String>,
+
+    /// Line in the PTX source.
+    pub line: Option<usize>,
+
+    /// Location in the Rust source, when the assembly has debug info.
+    pub source: Option<SourceLocation>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SourceLocation {
+    pub path: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// Checks the assembly for code, which is not expected in release builds.
+pub fn lint(module: &Module, config: &LintConfig) -> Vec<Lint> {
+    let mut lints = vec![];
+
+    if let (Some(max_target), Some(target)) = (config.max_target, module.sm_version()) {
+        if target > max_target {
+            lints.push(Lint {
+                kind: LintKind::TargetTooNew,
+                message: format!(
+                    "the assembly targets sm_{}, but sm_{} is the newest allowed",
+                    target, max_target
+                ),
+                function: None,
+                line: None,
+                source: None,
+            });
+        }
+    }
+
+    for function in module.functions.iter().filter(|function| function.defined) {
+        let lint = |kind, message: String, instruction: Option<&Instruction>| Lint {
+            kind,
+            message,
+            function: Some(function.name.clone()),
+            line: instruction.map(|instruction| instruction.line),
+            source: instruction.and_then(|instruction| source_location(module, instruction)),
+        };
+
+        if !config.debug && !is_panic_handler(function) {
+            if let Some(call) = find_call(function, "vprintf") {
+                lints.push(lint(
+                    LintKind::Printing,
+                    "`vprintf` is called in a release build".into(),
+                    Some(call),
+                ));
+            }
+        }
+
+        if !config.alloc {
+            if let Some(call) = find_call(function, "malloc") {
+                lints.push(lint(
+                    LintKind::Malloc,
+                    "`malloc` is called without the `alloc` feature".into(),
+                    Some(call),
+                ));
+            }
+        }
+
+        if !config.allow_local_memory {
+            let frames = function
+                .variables
+                .iter()
+                .filter(|variable| variable.space == StateSpace::Local);
+
+            for frame in frames {
+                lints.push(Lint {
+                    line: Some(frame.line),
+                    ..lint(
+                        LintKind::LocalMemory,
+                        format!(
+                            "local memory frame `{}` (stack or register spills)",
+                            frame.name
+                        ),
+                        None,
+                    )
+                });
+            }
+        }
+
+        if !config.allow_f64 {
+            let instruction = function.instructions.iter().find(|instruction| {
+                let mut parts = instruction.opcode.split('.');
+
+                matches!(parts.next(), Some(opcode) if F64_ARITHMETIC.contains(&opcode))
+                    && parts.any(|part| part == "f64")
+            });
+
+            if let Some(instruction) = instruction {
+                lints.push(lint(
+                    LintKind::DoublePrecision,
+                    format!(
+                        "double precision instruction `{}` is slow on consumer GPUs",
+                        instruction.opcode
+                    ),
+                    Some(instruction),
+                ));
+            }
+        }
+    }
+
+    lints
+}
+
+/// The panic handler is `rust_begin_unwind`, or a mangled `__rustc::rust_begin_unwind`
+/// with newer compilers.
+fn is_panic_handler(function: &Function) -> bool {
+    function.name == "rust_begin_unwind" || function.name.contains("17rust_begin_unwind")
+}
+
+fn find_call<'a>(function: &'a Function, callee: &str) -> Option<&'a Instruction> {
+    function.instructions.iter().find(|instruction| {
+        instruction.opcode.starts_with("call")
+            && instruction.operands.iter().any(|operand| operand == callee)
+    })
+}
+
+fn source_location(module: &Module, instruction: &Instruction) -> Option<SourceLocation> {
+    let loc = instruction.loc?;
+
+    Some(SourceLocation {
+        path: module.file_path(loc.file)?.to_owned(),
+        line: loc.line,
+        column: loc.column,
+    })
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.source, self.line) {
+            (Some(source), _) => write!(f, "{}:{}:{}: ", source.path, source.line, source.column)?,
+            (None, Some(line)) => write!(f, "PTX line {}: ", line)?,
+            (None, None) => {}
+        }
+
+        write!(f, "{}", self.message)?;
+
+        if let Some(function) = &self.function {
+            write!(f, " (in `{}`)", function)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+.version 6.0
+.target sm_61
+.address_size 64
+
+.extern .func (.param .b32 func_retval0) vprintf(.param .b64 vprintf_param_0, .param .b64 vprintf_param_1);
+.extern .func (.param .b64 func_retval0) malloc(.param .b64 malloc_param_0);
+
+.visible .entry example_kernel(.param .f64 example_kernel_param_0)
+{
+    .local .align 8 .b8 __local_depot0[16];
+    .reg .f64 %fd<3>;
+
+    .file 1 "/src/main.rs"
+    .loc 1 12 9
+    ld.param.f64 %fd1, [example_kernel_param_0];
+    mov.f64 %fd2, %fd1;
+    cvt.rn.f32.f64 %f1, %fd2;
+    st.global.f64 [%rd1], %fd2;
+    .loc 1 13 9
+    fma.rn.f64 %fd2, %fd1, %fd1, %fd2;
+    .loc 1 14 5
+    call.uni (retval0), vprintf, (param0, param1);
+    call.uni (retval0), malloc, (param0);
+    ret;
+}
+"#;
+
+    #[test]
+    fn test_release_lints() {
+        let module = Module::parse(SOURCE).unwrap();
+        let config = LintConfig {
+            max_target: Some(35),
+            ..LintConfig::default()
+        };
+
+        let lints = lint(&module, &config);
+
+        assert_eq!(
+            lints.iter().map(|lint| lint.kind).collect::<Vec<_>>(),
+            vec![
+                LintKind::TargetTooNew,
+                LintKind::Printing,
+                LintKind::Malloc,
+                LintKind::LocalMemory,
+                LintKind::DoublePrecision,
+            ]
+        );
+
+        assert_eq!(
+            lints
+                .iter()
+                .map(|lint| lint.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "the assembly targets sm_61, but sm_35 is the newest allowed",
+                "/src/main.rs:14:5: `vprintf` is called in a release build (in `example_kernel`)",
+                "/src/main.rs:14:5: `malloc` is called without the `alloc` feature (in `example_kernel`)",
+                "PTX line 11: local memory frame `__local_depot0` (stack or register spills) (in `example_kernel`)",
+                "/src/main.rs:13:9: double precision instruction `fma.rn.f64` is slow on consumer GPUs (in `example_kernel`)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_panic_handler_printing() {
+        let source = r#"
+.version 6.0
+.target sm_61
+.address_size 64
+
+.extern .func (.param .b32 func_retval0) vprintf(.param .b64 vprintf_param_0, .param .b64 vprintf_param_1);
+
+.func _RNvCsd2Vb6oJKeAS_7___rustc17rust_begin_unwind(.param .b64 rust_begin_unwind_param_0)
+{
+    call.uni (retval0), vprintf, (param0, param1);
+    trap;
+}
+
+.visible .entry checked_kernel(.param .u64 checked_kernel_param_0)
+{
+    call.uni _RNvCsd2Vb6oJKeAS_7___rustc17rust_begin_unwind, (param0);
+    ret;
+}
+"#;
+
+        let module = Module::parse(source).unwrap();
+        assert_eq!(lint(&module, &LintConfig::default()), vec![]);
+
+        let module =
+            Module::parse(&source.replace("17rust_begin_unwind", "12report_value")).unwrap();
+        let lints = lint(&module, &LintConfig::default());
+
+        assert_eq!(
+            lints.iter().map(|lint| lint.kind).collect::<Vec<_>>(),
+            vec![LintKind::Printing]
+        );
+    }
+
+    #[test]
+    fn test_allowed() {
+        let module = Module::parse(SOURCE).unwrap();
+        let config = LintConfig {
+            debug: true,
+            alloc: true,
+            allow_local_memory: true,
+            allow_f64: true,
+            max_target: Some(61),
+        };
+
+        assert_eq!(lint(&module, &config), vec![]);
+    }
+}
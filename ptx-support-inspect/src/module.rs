@@ -0,0 +1,279 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::InspectError;
+use crate::parser::parse_module;
+
+/// Parsed PTX assembly.
+#[derive(Debug, Default, PartialEq)]
+pub struct Module {
+    pub version: Option<Version>,
+
+    /// Target architecture and options, e.g. `["sm_30", "debug"]`.
+    pub target: Vec<String>,
+    pub address_size: Option<u32>,
+
+    /// Entry points and device functions, including declarations without a body.
+    pub functions: Vec<Function>,
+
+    /// Module-level variables.
+    pub variables: Vec<Variable>,
+
+    /// Source files referenced by `.loc` directives.
+    pub files: Vec<SourceFile>,
+}
+
+/// PTX ISA version (`.version 6.0`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionKind {
+    /// Kernel entry point (`.entry`).
+    Entry,
+
+    /// Device function (`.func`).
+    Func,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub kind: FunctionKind,
+
+    /// `.visible`, `.extern` or `.weak`.
+    pub linkage: Option<String>,
+
+    /// Whether the function has a body in the module.
+    pub defined: bool,
+
+    /// Return parameters of a `.func`.
+    pub returns: Vec<Param>,
+    pub params: Vec<Param>,
+
+    /// Performance tuning directives with their operands, e.g. `("maxntid", "256, 1, 1")`.
+    pub directives: Vec<(String, String)>,
+
+    pub registers: Vec<RegisterDecl>,
+
+    /// Variables declared inside the body.
+    pub variables: Vec<Variable>,
+    pub instructions: Vec<Instruction>,
+
+    /// Called functions, in order of the first call.
+    pub calls: Vec<String>,
+
+    /// Line of the declaration in the PTX source.
+    pub line: usize,
+}
+
+/// Parameter of a function or an entry point.
+#[derive(Debug, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub ty: String,
+    pub align: Option<u32>,
+    pub array: Option<usize>,
+}
+
+/// Register declaration, e.g. `.reg .f64 %fd<5>;`.
+#[derive(Debug, PartialEq)]
+pub struct RegisterDecl {
+    pub ty: String,
+    pub name: String,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateSpace {
+    Global,
+    Shared,
+    Const,
+    Local,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub space: StateSpace,
+    pub ty: String,
+    pub linkage: Option<String>,
+    pub align: Option<u32>,
+
+    /// Length of an array, `Some(0)` for unsized arrays (`name[]`).
+    pub array: Option<usize>,
+    pub line: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Instruction {
+    /// Guard predicate without `@`, e.g. `%p1` or `!%p1`.
+    pub predicate: Option<String>,
+
+    /// Opcode with all modifiers, e.g. `ld.global.nc.f32`.
+    pub opcode: String,
+    pub operands: Vec<String>,
+    pub line: usize,
+
+    /// Location from the last `.loc` directive before the instruction.
+    pub loc: Option<Loc>,
+}
+
+/// Source location of a `.loc` directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Loc {
+    pub file: u32,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// Source file of a `.file` directive.
+#[derive(Debug, PartialEq)]
+pub struct SourceFile {
+    pub index: u32,
+    pub path: String,
+}
+
+impl Module {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, InspectError> {
+        let source = fs::read_to_string(path).map_err(|error| InspectError::Io {
+            reason: error.to_string(),
+        })?;
+
+        Module::parse(&source)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, InspectError> {
+        parse_module(source)
+    }
+
+    /// Defined kernel entry points.
+    pub fn entries(&self) -> impl Iterator<Item = &Function> {
+        self.functions
+            .iter()
+            .filter(|function| function.kind == FunctionKind::Entry && function.defined)
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&Function> {
+        self.entries().find(|function| function.name == name)
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&Variable> {
+        self.variables.iter().find(|variable| variable.name == name)
+    }
+
+    pub fn file_path(&self, index: u32) -> Option<&str> {
+        self.files
+            .iter()
+            .find(|file| file.index == index)
+            .map(|file| &file.path[..])
+    }
+
+    /// Numeric SM version of the target, e.g. `35` for `sm_35`.
+    pub fn sm_version(&self) -> Option<u32> {
+        self.target
+            .iter()
+            .filter_map(|target| target.trim_start_matches("sm_").parse().ok())
+            .next()
+    }
+}
+
+impl Function {
+    /// Amount of declared registers of the type, e.g. `.f32`.
+    pub fn register_count(&self, ty: &str) -> u32 {
+        self.registers
+            .iter()
+            .filter(|decl| decl.ty == ty)
+            .map(|decl| decl.count)
+            .sum()
+    }
+
+    /// Checks whether any register or instruction has the type, e.g. `.f64`.
+    pub fn uses_type(&self, ty: &str) -> bool {
+        let suffix = ty.trim_start_matches('.');
+
+        self.registers.iter().any(|decl| decl.ty == ty)
+            || self.instructions.iter().any(|instruction| {
+                instruction
+                    .opcode
+                    .split('.')
+                    .skip(1)
+                    .any(|part| part == suffix)
+            })
+    }
+
+    pub fn calls_function(&self, name: &str) -> bool {
+        self.calls.iter().any(|call| call == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+.version 6.0
+.target sm_35
+.address_size 64
+
+.extern .func (.param .b32 func_retval0) vprintf(.param .b64 vprintf_param_0, .param .b64 vprintf_param_1);
+
+.visible .entry scale(.param .f32 scale_param_0)
+{
+    .reg .f32 %f<3>;
+    .reg .f64 %fd<2>;
+
+    ld.param.f32 %f1, [scale_param_0];
+    cvt.f64.f32 %fd1, %f1;
+    ret;
+}
+
+.visible .entry print()
+{
+    .reg .b32 %r<2>;
+
+    { // callseq 0
+    .param .b64 param0;
+    call.uni (retval0), vprintf, (param0, param1);
+    } // callseq 0
+    ret;
+}
+"#;
+
+    #[test]
+    fn test_queries() {
+        let module = Module::parse(SOURCE).unwrap();
+
+        assert_eq!(module.sm_version(), Some(35));
+        assert_eq!(
+            module
+                .entries()
+                .map(|entry| &entry.name[..])
+                .collect::<Vec<_>>(),
+            vec!["scale", "print"]
+        );
+
+        let scale = module.entry("scale").unwrap();
+        let print = module.entry("print").unwrap();
+
+        assert_eq!(scale.register_count(".f32"), 3);
+        assert!(scale.uses_type(".f64"));
+        assert!(!print.uses_type(".f64"));
+
+        assert!(print.calls_function("vprintf"));
+        assert!(!scale.calls_function("vprintf"));
+
+        assert_eq!(module.entry("vprintf"), None);
+        assert_eq!(
+            module.function("vprintf").map(|func| func.defined),
+            Some(false)
+        );
+    }
+}
@@ -0,0 +1,405 @@
+use std::cmp::min;
+use std::fmt;
+
+use crate::module::{Function, Module, StateSpace};
+
+const WARP_SIZE: u32 = 32;
+
+/// Per-SM resources of an architecture, from the CUDA occupancy calculator.
+#[derive(Debug, PartialEq)]
+pub struct ArchLimits {
+    /// SM version, e.g. `35` for `sm_35`.
+    pub sm: u32,
+
+    pub max_threads_per_block: u32,
+    pub max_warps_per_sm: u32,
+    pub max_blocks_per_sm: u32,
+
+    pub registers_per_sm: u32,
+    pub max_registers_per_thread: u32,
+
+    /// Registers are allocated per warp in chunks of this size.
+    pub register_allocation_unit: u32,
+
+    /// Warps are allocated registers in groups of this size.
+    pub warp_allocation_granularity: u32,
+
+    pub shared_memory_per_sm: u32,
+    pub max_shared_memory_per_block: u32,
+    pub shared_memory_allocation_unit: u32,
+
+    /// Shared memory reserved by the driver for each block.
+    pub reserved_shared_memory_per_block: u32,
+}
+
+macro_rules! arch_limits {
+    ($(
+        $sm:expr => (
+            $threads_per_sm:expr, $blocks_per_sm:expr, $registers_per_sm:expr, $warp_granularity:expr,
+            $shared_per_sm:expr, $shared_per_block:expr, $shared_unit:expr, $shared_reserved:expr
+        ),
+    )*) => {
+        /// Supported architectures, from `sm_35` to `sm_90`.
+        pub const ARCH_LIMITS: &[ArchLimits] = &[$(
+            ArchLimits {
+                sm: $sm,
+                max_threads_per_block: 1024,
+                max_warps_per_sm: $threads_per_sm / WARP_SIZE,
+                max_blocks_per_sm: $blocks_per_sm,
+                registers_per_sm: $registers_per_sm,
+                max_registers_per_thread: 255,
+                register_allocation_unit: 256,
+                warp_allocation_granularity: $warp_granularity,
+                shared_memory_per_sm: $shared_per_sm,
+                max_shared_memory_per_block: $shared_per_block,
+                shared_memory_allocation_unit: $shared_unit,
+                reserved_shared_memory_per_block: $shared_reserved,
+            },
+        )*];
+    };
+}
+
+arch_limits! {
+    35 => (2048, 16, 65536, 4, 49152, 49152, 256, 0),
+    37 => (2048, 16, 131_072, 4, 114_688, 49152, 256, 0),
+    50 => (2048, 32, 65536, 4, 65536, 49152, 256, 0),
+    52 => (2048, 32, 65536, 4, 98304, 49152, 256, 0),
+    53 => (2048, 32, 65536, 4, 65536, 49152, 256, 0),
+    60 => (2048, 32, 65536, 2, 65536, 49152, 256, 0),
+    61 => (2048, 32, 65536, 4, 98304, 49152, 256, 0),
+    62 => (2048, 32, 65536, 4, 65536, 49152, 256, 0),
+    70 => (2048, 32, 65536, 4, 98304, 98304, 256, 0),
+    72 => (2048, 32, 65536, 4, 98304, 98304, 256, 0),
+    75 => (1024, 16, 65536, 4, 65536, 65536, 256, 0),
+    80 => (2048, 32, 65536, 4, 167_936, 166_912, 128, 1024),
+    86 => (1536, 16, 65536, 4, 102_400, 101_376, 128, 1024),
+    87 => (2048, 16, 65536, 4, 167_936, 166_912, 128, 1024),
+    89 => (1536, 24, 65536, 4, 102_400, 101_376, 128, 1024),
+    90 => (2048, 32, 65536, 4, 233_472, 232_448, 128, 1024),
+}
+
+impl ArchLimits {
+    pub fn find(sm: u32) -> Option<&'static ArchLimits> {
+        ARCH_LIMITS.iter().find(|limits| limits.sm == sm)
+    }
+}
+
+/// Resources used by a single block of a kernel.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct KernelResources {
+    pub registers_per_thread: u32,
+
+    /// Statically declared shared memory in bytes.
+    pub shared_memory: u32,
+
+    /// Shared memory in bytes requested at launch.
+    pub dynamic_shared_memory: u32,
+
+    /// Total amount of threads allowed by `.maxntid`.
+    pub max_threads: Option<u32>,
+
+    /// Total amount of threads required by `.reqntid`.
+    pub required_threads: Option<u32>,
+}
+
+impl KernelResources {
+    /// Estimates resources of a kernel from its assembly.
+    ///
+    /// PTX uses virtual registers, so the register count is only an estimate: `ptxas` usually
+    /// allocates fewer registers by reusing them, but can need more for temporaries.
+    /// It never exceeds the limit of the architecture, since `ptxas` spills to local memory
+    /// instead. The count should be overridden when the real value is known.
+    pub fn from_function(module: &Module, function: &Function, limits: &ArchLimits) -> Self {
+        let registers_per_thread = function
+            .registers
+            .iter()
+            .map(|decl| decl.count * register_slots(&decl.ty))
+            .sum::<u32>()
+            .min(limits.max_registers_per_thread);
+
+        let module_shared = module
+            .variables
+            .iter()
+            .filter(|variable| is_referenced(function, &variable.name));
+
+        let shared_memory = function
+            .variables
+            .iter()
+            .chain(module_shared)
+            .filter(|variable| variable.space == StateSpace::Shared)
+            .map(|variable| type_size(&variable.ty) * variable.array.unwrap_or(1) as u32)
+            .sum();
+
+        let threads = |name| {
+            function
+                .directives
+                .iter()
+                .find(|(directive, _)| directive == name)
+                .map(|(_, operands)| {
+                    operands
+                        .split(',')
+                        .filter_map(|dim| dim.trim().parse::<u32>().ok())
+                        .product()
+                })
+        };
+
+        KernelResources {
+            registers_per_thread,
+            shared_memory,
+            dynamic_shared_memory: 0,
+            max_threads: threads("maxntid"),
+            required_threads: threads("reqntid"),
+        }
+    }
+}
+
+/// Resource, which limits the amount of active blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limiter {
+    BlocksPerSm,
+    WarpsPerSm,
+    Registers,
+    SharedMemory,
+
+    /// The block size is not allowed by the kernel or the architecture.
+    BlockSize,
+}
+
+/// Theoretical occupancy of a kernel for a block size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occupancy {
+    pub block_size: u32,
+    pub active_blocks: u32,
+    pub active_warps: u32,
+
+    /// Ratio of active warps to the maximum amount of warps per SM.
+    pub occupancy: f64,
+    pub limiter: Limiter,
+}
+
+pub fn occupancy(limits: &ArchLimits, resources: &KernelResources, block_size: u32) -> Occupancy {
+    let warps_per_block = block_size.div_ceil(WARP_SIZE);
+
+    let by_block_size = if block_size == 0
+        || block_size > limits.max_threads_per_block
+        || resources.max_threads.is_some_and(|max| block_size > max)
+        || resources
+            .required_threads
+            .is_some_and(|req| block_size != req)
+    {
+        0
+    } else {
+        u32::MAX
+    };
+
+    let by_warps = match warps_per_block {
+        0 => 0,
+        warps_per_block => limits.max_warps_per_sm / warps_per_block,
+    };
+
+    let by_registers = match resources.registers_per_thread {
+        0 => u32::MAX,
+        registers if registers > limits.max_registers_per_thread => 0,
+
+        registers => {
+            let per_warp = round_up(registers * WARP_SIZE, limits.register_allocation_unit);
+            let granularity = limits.warp_allocation_granularity;
+            let warps = limits.registers_per_sm / per_warp / granularity * granularity;
+
+            warps / warps_per_block.max(1)
+        }
+    };
+
+    let by_shared_memory = {
+        let shared_memory = resources.shared_memory
+            + resources.dynamic_shared_memory
+            + limits.reserved_shared_memory_per_block;
+
+        let per_block = round_up(shared_memory, limits.shared_memory_allocation_unit);
+
+        if per_block == 0 {
+            u32::MAX
+        } else if per_block
+            > limits.max_shared_memory_per_block + limits.reserved_shared_memory_per_block
+        {
+            0
+        } else {
+            limits.shared_memory_per_sm / per_block
+        }
+    };
+
+    let (active_blocks, limiter) = [
+        (limits.max_blocks_per_sm, Limiter::BlocksPerSm),
+        (by_warps, Limiter::WarpsPerSm),
+        (by_registers, Limiter::Registers),
+        (by_shared_memory, Limiter::SharedMemory),
+        (by_block_size, Limiter::BlockSize),
+    ]
+    .iter()
+    .cloned()
+    .min_by_key(|(blocks, _)| *blocks)
+    .unwrap();
+
+    let active_warps = min(active_blocks * warps_per_block, limits.max_warps_per_sm);
+
+    Occupancy {
+        block_size,
+        active_blocks,
+        active_warps,
+        occupancy: f64::from(active_warps) / f64::from(limits.max_warps_per_sm),
+        limiter,
+    }
+}
+
+/// Occupancy for every block size from a single warp up to the maximum block size.
+pub fn occupancy_table(limits: &ArchLimits, resources: &KernelResources) -> Vec<Occupancy> {
+    (1..=limits.max_threads_per_block / WARP_SIZE)
+        .map(|warps| occupancy(limits, resources, warps * WARP_SIZE))
+        .collect()
+}
+
+impl fmt::Display for Limiter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Limiter::BlocksPerSm => "blocks per SM",
+            Limiter::WarpsPerSm => "warps per SM",
+            Limiter::Registers => "registers",
+            Limiter::SharedMemory => "shared memory",
+            Limiter::BlockSize => "block size",
+        })
+    }
+}
+
+/// Amount of 32-bit registers taken by a register of the type.
+fn register_slots(ty: &str) -> u32 {
+    match ty {
+        ".pred" => 0,
+        ty if ty.ends_with("64") => 2,
+        _ => 1,
+    }
+}
+
+/// Size in bytes of a fundamental type, e.g. `4` for `.f32`.
+fn type_size(ty: &str) -> u32 {
+    match ty {
+        ".f16x2" => 4,
+        ty => {
+            let bits = ty.trim_start_matches(|chr: char| !chr.is_ascii_digit());
+            bits.parse::<u32>().map_or(0, |bits| bits / 8)
+        }
+    }
+}
+
+fn is_referenced(function: &Function, name: &str) -> bool {
+    function.instructions.iter().any(|instruction| {
+        instruction.operands.iter().any(|operand| {
+            operand
+                .split(|chr: char| !(chr.is_alphanumeric() || chr == '_' || chr == '$'))
+                .any(|word| word == name)
+        })
+    })
+}
+
+fn round_up(value: u32, unit: u32) -> u32 {
+    value.div_ceil(unit) * unit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+.version 6.0
+.target sm_61
+.address_size 64
+
+.shared .align 4 .b8 tile[4096];
+
+.visible .entry blur(.param .u64 blur_param_0)
+.maxntid 256, 1, 1
+{
+    .reg .pred %p<2>;
+    .reg .f32 %f<20>;
+    .reg .b64 %rd<6>;
+
+    mov.u64 %rd1, tile;
+    st.shared.f32 [tile+4], %f1;
+    ret;
+}
+"#;
+
+    #[test]
+    fn test_kernel_resources() {
+        let module = Module::parse(SOURCE).unwrap();
+        let limits = ArchLimits::find(61).unwrap();
+        let resources =
+            KernelResources::from_function(&module, module.entry("blur").unwrap(), limits);
+
+        assert_eq!(
+            resources,
+            KernelResources {
+                registers_per_thread: 32,
+                shared_memory: 4096,
+                dynamic_shared_memory: 0,
+                max_threads: Some(256),
+                required_threads: None,
+            }
+        );
+
+        let module = Module::parse(&SOURCE.replace("%rd<6>", "%rd<200>")).unwrap();
+        let resources =
+            KernelResources::from_function(&module, module.entry("blur").unwrap(), limits);
+
+        assert_eq!(resources.registers_per_thread, 255);
+    }
+
+    #[test]
+    fn test_occupancy() {
+        let limits = ArchLimits::find(61).unwrap();
+        let mut resources = KernelResources::default();
+
+        let check = |resources: &KernelResources, block_size| {
+            let result = occupancy(limits, resources, block_size);
+            (result.active_blocks, result.active_warps, result.limiter)
+        };
+
+        assert_eq!(check(&resources, 32), (32, 32, Limiter::BlocksPerSm));
+        assert_eq!(check(&resources, 256), (8, 64, Limiter::WarpsPerSm));
+        assert_eq!(check(&resources, 2048), (0, 0, Limiter::BlockSize));
+
+        resources.registers_per_thread = 64;
+        assert_eq!(check(&resources, 256), (4, 32, Limiter::Registers));
+
+        resources.registers_per_thread = 0;
+        resources.shared_memory = 40000;
+        assert_eq!(check(&resources, 256), (2, 16, Limiter::SharedMemory));
+
+        resources.shared_memory = 0;
+        resources.required_threads = Some(128);
+        assert_eq!(check(&resources, 256), (0, 0, Limiter::BlockSize));
+        assert_eq!(check(&resources, 128), (16, 64, Limiter::WarpsPerSm));
+
+        assert_eq!(occupancy(limits, &resources, 128).occupancy, 1.0);
+        assert_eq!(occupancy_table(limits, &resources).len(), 32);
+    }
+
+    #[test]
+    fn test_reserved_shared_memory() {
+        let limits = ArchLimits::find(80).unwrap();
+        let resources = KernelResources {
+            shared_memory: 48 * 1024,
+            ..KernelResources::default()
+        };
+
+        let result = occupancy(limits, &resources, 1024);
+
+        assert_eq!(result.active_blocks, 2);
+        assert_eq!(result.limiter, Limiter::WarpsPerSm);
+        assert_eq!(occupancy(limits, &resources, 128).active_blocks, 3);
+        assert_eq!(
+            occupancy(limits, &resources, 128).limiter,
+            Limiter::SharedMemory
+        );
+        assert_eq!(ArchLimits::find(30), None);
+    }
+}
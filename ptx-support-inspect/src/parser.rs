@@ -0,0 +1,869 @@
+use std::vec;
+
+use crate::error::InspectError;
+use crate::module::*;
+
+/// Piece of the source: a statement terminated by `;`, a header before `{`, or a `}`.
+///
+/// Module header directives, `.loc`, `.file` and labels are not terminated by `;`,
+/// so they take the whole line.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Statement(String, usize),
+    Open(String, usize),
+    Close(usize),
+}
+
+const STATE_SPACES: &[(&str, StateSpace)] = &[
+    (".global", StateSpace::Global),
+    (".shared", StateSpace::Shared),
+    (".const", StateSpace::Const),
+    (".local", StateSpace::Local),
+];
+
+const LINE_DIRECTIVES: &[&str] = &[".version", ".target", ".address_size", ".loc", ".file"];
+
+const LINKAGES: &[&str] = &[".visible", ".extern", ".weak", ".common"];
+
+pub fn parse_module(source: &str) -> Result<Module, InspectError> {
+    let mut module = Module::default();
+    let mut tokens = tokenize(source).into_iter();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Statement(text, line) => parse_module_statement(&mut module, &text, line)?,
+
+            Token::Open(ref header, line) if header.starts_with(".section") => {
+                skip_block(&mut tokens, line)?;
+            }
+
+            Token::Open(header, line) => {
+                let mut function = parse_header(&header, line)?;
+
+                parse_body(&mut function, &mut module.files, &mut tokens)?;
+                module.functions.push(function);
+            }
+
+            Token::Close(line) => return Err(parse_error(line, "unexpected `}`")),
+        }
+    }
+
+    Ok(module)
+}
+
+fn parse_error(line: usize, reason: &str) -> InspectError {
+    InspectError::Parse {
+        line,
+        reason: reason.to_owned(),
+    }
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut start_line = 0;
+    let mut initializer_depth = 0;
+
+    for (index, line) in strip_comments(source).lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim();
+
+        let whole_line = LINE_DIRECTIVES.contains(&first_word(trimmed))
+            || (trimmed.ends_with(':') && !trimmed.contains(char::is_whitespace));
+
+        if current.trim().is_empty() && whole_line {
+            tokens.push(Token::Statement(trimmed.to_owned(), line_number));
+            continue;
+        }
+
+        for chr in line.chars() {
+            if current.trim().is_empty() && !chr.is_whitespace() {
+                start_line = line_number;
+            }
+
+            match chr {
+                ';' if initializer_depth == 0 => {
+                    tokens.push(Token::Statement(current.trim().to_owned(), start_line));
+                    current.clear();
+                }
+
+                // Braces of initializers: `.b8 name[2] = {1, 2};`.
+                '{' if current.contains('=') => {
+                    initializer_depth += 1;
+                    current.push(chr);
+                }
+
+                '}' if initializer_depth > 0 => {
+                    initializer_depth -= 1;
+                    current.push(chr);
+                }
+
+                '{' => {
+                    let line = match current.trim().is_empty() {
+                        true => line_number,
+                        false => start_line,
+                    };
+
+                    tokens.push(Token::Open(current.trim().to_owned(), line));
+                    current.clear();
+                }
+
+                '}' => {
+                    tokens.push(Token::Close(line_number));
+                    current.clear();
+                }
+
+                chr => current.push(chr),
+            }
+        }
+
+        current.push('\n');
+    }
+
+    tokens
+}
+
+/// Removes `//` and `/* */` comments, keeping line breaks.
+fn strip_comments(source: &str) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(chr) = chars.next() {
+        match chr {
+            '"' => {
+                in_string = !in_string;
+                result.push(chr);
+            }
+
+            '/' if !in_string && chars.peek() == Some(&'/') => {
+                while let Some(&next) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+
+                    chars.next();
+                }
+            }
+
+            '/' if !in_string && chars.peek() == Some(&'*') => {
+                chars.next();
+
+                let mut previous = ' ';
+
+                for next in &mut chars {
+                    if next == '\n' {
+                        result.push(next);
+                    }
+
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+
+                    previous = next;
+                }
+            }
+
+            '\n' => {
+                in_string = false;
+                result.push(chr);
+            }
+
+            chr => result.push(chr),
+        }
+    }
+
+    result
+}
+
+fn skip_block(tokens: &mut vec::IntoIter<Token>, line: usize) -> Result<(), InspectError> {
+    let mut depth = 1;
+
+    for token in tokens {
+        match token {
+            Token::Open(..) => depth += 1,
+            Token::Close(_) if depth == 1 => return Ok(()),
+            Token::Close(_) => depth -= 1,
+            Token::Statement(..) => {}
+        }
+    }
+
+    Err(parse_error(line, "unterminated block"))
+}
+
+fn parse_module_statement(
+    module: &mut Module,
+    text: &str,
+    line: usize,
+) -> Result<(), InspectError> {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+
+    match words.first() {
+        Some(&".version") => {
+            let version = words
+                .get(1)
+                .and_then(|version| parse_version(version))
+                .ok_or_else(|| parse_error(line, "malformed `.version`"))?;
+
+            module.version = Some(version);
+        }
+
+        Some(&".target") => {
+            module.target = text[".target".len()..]
+                .split(',')
+                .map(|target| target.trim().to_owned())
+                .filter(|target| !target.is_empty())
+                .collect();
+        }
+
+        Some(&".address_size") => {
+            let size = words
+                .get(1)
+                .and_then(|size| size.parse().ok())
+                .ok_or_else(|| parse_error(line, "malformed `.address_size`"))?;
+
+            module.address_size = Some(size);
+        }
+
+        Some(&".file") => module.files.push(parse_file(text, line)?),
+
+        _ if words.contains(&".func") || words.contains(&".entry") => {
+            module.functions.push(parse_header(text, line)?);
+        }
+
+        _ if words.iter().any(|word| is_state_space(word)) => {
+            module.variables.push(parse_variable(text, line)?);
+        }
+
+        // Other directives (e.g. `.pragma`) don't affect the model.
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn parse_version(version: &str) -> Option<Version> {
+    let mut parts = version.split('.');
+
+    Some(Version {
+        major: parts.next()?.parse().ok()?,
+        minor: parts.next()?.parse().ok()?,
+    })
+}
+
+fn first_word(text: &str) -> &str {
+    text.split_whitespace().next().unwrap_or("")
+}
+
+fn is_state_space(word: &str) -> bool {
+    STATE_SPACES.iter().any(|(name, _)| *name == word)
+}
+
+/// Parses `.visible .entry name(params) directives` or
+/// `.func (return params) name(params) directives`.
+fn parse_header(header: &str, line: usize) -> Result<Function, InspectError> {
+    let malformed = || parse_error(line, "malformed function declaration");
+
+    let (kind, keyword) = match (header.find(".entry"), header.find(".func")) {
+        (Some(position), _) => (FunctionKind::Entry, position),
+        (None, Some(position)) => (FunctionKind::Func, position),
+        (None, None) => return Err(parse_error(line, "expected `.entry` or `.func`")),
+    };
+
+    let linkage = header[..keyword]
+        .split_whitespace()
+        .find(|word| LINKAGES.contains(word))
+        .map(str::to_owned);
+
+    let mut rest = header[keyword..]
+        .split_once(char::is_whitespace)
+        .map_or("", |(_, rest)| rest.trim_start());
+
+    let mut returns = vec![];
+
+    if kind == FunctionKind::Func && rest.starts_with('(') {
+        let (group, after) = split_group(rest).ok_or_else(malformed)?;
+
+        returns = parse_params(group, line)?;
+        rest = after.trim_start();
+    }
+
+    let name_end = rest
+        .find(|chr: char| chr == '(' || chr.is_whitespace())
+        .unwrap_or(rest.len());
+
+    let name = rest[..name_end].to_owned();
+    rest = rest[name_end..].trim_start();
+
+    if name.is_empty() {
+        return Err(malformed());
+    }
+
+    let mut params = vec![];
+
+    if rest.starts_with('(') {
+        let (group, after) = split_group(rest).ok_or_else(malformed)?;
+
+        params = parse_params(group, line)?;
+        rest = after;
+    }
+
+    let directives = rest
+        .lines()
+        .map(str::trim)
+        .filter(|directive| directive.starts_with('.'))
+        .map(|directive| {
+            let mut parts = directive[1..].splitn(2, char::is_whitespace);
+            let name = parts.next().unwrap_or("").to_owned();
+            let operands = parts.next().unwrap_or("").trim().to_owned();
+
+            (name, operands)
+        })
+        .collect();
+
+    Ok(Function {
+        name,
+        kind,
+        linkage,
+        defined: false,
+        returns,
+        params,
+        directives,
+        registers: vec![],
+        variables: vec![],
+        instructions: vec![],
+        calls: vec![],
+        line,
+    })
+}
+
+/// Splits `(inner) rest` into `inner` and `rest`.
+fn split_group(text: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+
+    for (position, chr) in text.char_indices() {
+        match chr {
+            '(' => depth += 1,
+            ')' if depth == 1 => return Some((&text[1..position], &text[position + 1..])),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn parse_params(group: &str, line: usize) -> Result<Vec<Param>, InspectError> {
+    group
+        .split(',')
+        .map(str::trim)
+        .filter(|param| !param.is_empty())
+        .map(|param| parse_param(param, line))
+        .collect()
+}
+
+/// Parses `.param .align 8 .b8 name[16]` or `.param .u64 .ptr .global .align 4 name`.
+fn parse_param(text: &str, line: usize) -> Result<Param, InspectError> {
+    let mut words = text.split_whitespace();
+    let mut ty = None;
+    let mut align = None;
+    let mut name = None;
+
+    while let Some(word) = words.next() {
+        match word {
+            ".param" | ".reg" | ".ptr" => {}
+            ".align" => align = words.next().and_then(|align| align.parse().ok()),
+            word if is_state_space(word) => {}
+            word if word.starts_with('.') => ty = Some(word.to_owned()),
+            word => name = Some(word),
+        }
+    }
+
+    let malformed = || parse_error(line, "malformed parameter declaration");
+    let (name, array) = parse_name(name.ok_or_else(malformed)?, line)?;
+
+    Ok(Param {
+        name,
+        ty: ty.ok_or_else(malformed)?,
+        align,
+        array,
+    })
+}
+
+/// Parses `name`, `name[16]` or `name[]`.
+fn parse_name(text: &str, line: usize) -> Result<(String, Option<usize>), InspectError> {
+    match text.find('[') {
+        None => Ok((text.to_owned(), None)),
+
+        Some(start) => {
+            let length = text[start + 1..].trim_end_matches(']').trim();
+
+            let length = match length {
+                "" => 0,
+                length => length
+                    .parse()
+                    .map_err(|_| parse_error(line, "malformed array length"))?,
+            };
+
+            Ok((text[..start].to_owned(), Some(length)))
+        }
+    }
+}
+
+/// Parses `.visible .global .align 4 .b8 name[16] = {...}`.
+fn parse_variable(text: &str, line: usize) -> Result<Variable, InspectError> {
+    let declaration = text.split('=').next().unwrap_or("");
+    let mut words = declaration.split_whitespace();
+
+    let mut linkage = None;
+    let mut space = None;
+    let mut align = None;
+    let mut ty = None;
+    let mut name = None;
+
+    while let Some(word) = words.next() {
+        match word {
+            ".align" => align = words.next().and_then(|align| align.parse().ok()),
+            word if LINKAGES.contains(&word) => linkage = Some(word.to_owned()),
+            word if is_state_space(word) => {
+                space = STATE_SPACES
+                    .iter()
+                    .find(|(name, _)| *name == word)
+                    .map(|(_, space)| *space);
+            }
+
+            word if word.starts_with('.') => ty = Some(word.to_owned()),
+            word => name = Some(word),
+        }
+    }
+
+    let malformed = || parse_error(line, "malformed variable declaration");
+    let (name, array) = parse_name(name.ok_or_else(malformed)?, line)?;
+
+    Ok(Variable {
+        name,
+        space: space.ok_or_else(malformed)?,
+        ty: ty.ok_or_else(malformed)?,
+        linkage,
+        align,
+        array,
+        line,
+    })
+}
+
+/// Parses `.file 1 "path"`, optionally followed by a timestamp and a size.
+fn parse_file(text: &str, line: usize) -> Result<SourceFile, InspectError> {
+    let malformed = || parse_error(line, "malformed `.file`");
+
+    let index = text
+        .split_whitespace()
+        .nth(1)
+        .and_then(|index| index.parse().ok())
+        .ok_or_else(malformed)?;
+
+    let mut parts = text.split('"');
+    let path = parts.nth(1).ok_or_else(malformed)?.to_owned();
+
+    Ok(SourceFile { index, path })
+}
+
+/// Parses `.loc 1 8 5`, ignoring `function_name` and `inlined_at` options.
+fn parse_loc(text: &str, line: usize) -> Result<Loc, InspectError> {
+    let mut numbers = text
+        .split(|chr: char| chr.is_whitespace() || chr == ',')
+        .filter(|part| !part.is_empty())
+        .skip(1)
+        .map(|number| number.parse().ok());
+
+    let mut next = || {
+        numbers
+            .next()
+            .and_then(|number| number)
+            .ok_or_else(|| parse_error(line, "malformed `.loc`"))
+    };
+
+    Ok(Loc {
+        file: next()?,
+        line: next()?,
+        column: next()?,
+    })
+}
+
+fn parse_body(
+    function: &mut Function,
+    files: &mut Vec<SourceFile>,
+    tokens: &mut vec::IntoIter<Token>,
+) -> Result<(), InspectError> {
+    let mut depth = 1;
+    let mut loc = None;
+
+    function.defined = true;
+
+    for token in tokens {
+        match token {
+            Token::Statement(ref text, line) if first_word(text) == ".file" => {
+                files.push(parse_file(text, line)?);
+            }
+
+            Token::Statement(ref text, line) if first_word(text) == ".loc" => {
+                loc = Some(parse_loc(text, line)?);
+            }
+
+            Token::Statement(text, line) => parse_body_statement(function, &text, line, loc)?,
+
+            // Nested scopes, e.g. around call sequences.
+            Token::Open(..) => depth += 1,
+            Token::Close(_) if depth == 1 => return Ok(()),
+            Token::Close(_) => depth -= 1,
+        }
+    }
+
+    Err(parse_error(function.line, "unterminated function body"))
+}
+
+fn parse_body_statement(
+    function: &mut Function,
+    text: &str,
+    line: usize,
+    loc: Option<Loc>,
+) -> Result<(), InspectError> {
+    match first_word(text) {
+        "" => {}
+
+        ".reg" => function.registers.extend(parse_registers(text, line)?),
+
+        first if is_state_space(first) => function.variables.push(parse_variable(text, line)?),
+
+        // `.pragma`, call parameters and labels.
+        first if first.starts_with('.') || first.ends_with(':') => {}
+
+        _ => {
+            let instruction = parse_instruction(text, line, loc);
+
+            if instruction.opcode.starts_with("call") {
+                let callee = instruction
+                    .operands
+                    .iter()
+                    .find(|operand| !operand.starts_with('('));
+
+                if let Some(callee) = callee {
+                    if !function.calls_function(callee) {
+                        function.calls.push(callee.clone());
+                    }
+                }
+            }
+
+            function.instructions.push(instruction);
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses `.reg .f32 %f<5>` and `.reg .b64 %SP, %SPL`.
+fn parse_registers(text: &str, line: usize) -> Result<Vec<RegisterDecl>, InspectError> {
+    let malformed = || parse_error(line, "malformed register declaration");
+    let mut words = text.splitn(3, char::is_whitespace).skip(1);
+
+    let ty = words.next().ok_or_else(malformed)?.to_owned();
+    let names = words.next().ok_or_else(malformed)?;
+
+    names
+        .split(',')
+        .map(str::trim)
+        .map(|name| match name.find('<') {
+            None => Ok(RegisterDecl {
+                ty: ty.clone(),
+                name: name.to_owned(),
+                count: 1,
+            }),
+
+            Some(start) => Ok(RegisterDecl {
+                ty: ty.clone(),
+                name: name[..start].to_owned(),
+                count: name[start + 1..]
+                    .trim_end_matches('>')
+                    .parse()
+                    .map_err(|_| malformed())?,
+            }),
+        })
+        .collect()
+}
+
+fn parse_instruction(text: &str, line: usize, loc: Option<Loc>) -> Instruction {
+    let mut rest = text.trim();
+    let mut predicate = None;
+
+    if rest.starts_with('@') {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+
+        predicate = Some(rest[1..end].to_owned());
+        rest = rest[end..].trim_start();
+    }
+
+    let opcode_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+
+    Instruction {
+        predicate,
+        opcode: rest[..opcode_end].to_owned(),
+        operands: split_operands(&rest[opcode_end..]),
+        line,
+        loc,
+    }
+}
+
+/// Splits operands by commas outside of parentheses, brackets and braces.
+fn split_operands(text: &str) -> Vec<String> {
+    let mut operands = vec![];
+    let mut current = String::new();
+    let mut depth = 0;
+
+    for chr in text.chars() {
+        match chr {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                operands.push(normalize_whitespace(&current));
+                current.clear();
+                continue;
+            }
+
+            _ => {}
+        }
+
+        current.push(chr);
+    }
+
+    if !current.trim().is_empty() {
+        operands.push(normalize_whitespace(&current));
+    }
+
+    operands
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"//
+// Generated by LLVM NVPTX Back-End
+//
+
+.version 6.0
+.target sm_30
+.address_size 64
+
+.extern .func  (.param .b32 func_retval0) vprintf
+(
+	.param .b64 vprintf_param_0,
+	.param .b64 vprintf_param_1
+)
+;
+.global .align 1 .b8 $str[3] = {37, 100, 0};
+.extern .shared .align 4 .b8 dynamic_shared[];
+.visible .const .align 4 .f32 WEIGHTS[4];
+
+.visible .entry example_kernel(
+	.param .f64 example_kernel_param_0,
+	.param .align 8 .b8 example_kernel_param_1[16]
+)
+.maxntid 64, 1, 1
+{
+	.local .align 8 .b8 	__local_depot0[8];
+	.reg .b64 	%SP;
+	.reg .pred 	%p<2>;
+	.reg .f64 	%fd<3>;
+	.reg .b64 	%rd<4>;
+
+	.file	1 "/src/main.rs"
+	.loc	1 8 0
+	ld.param.f64 	%fd1, [example_kernel_param_0];
+	/* multi-line
+	   comment */
+	setp.gt.f64 	%p1, %fd1, 0d0000000000000000;
+	@!%p1 bra 	LBB0_2;
+LBB0_1:
+	{ // callseq 0, 0
+	.reg .b32 temp_param_reg;
+	.param .b64 param0;
+	st.param.b64 	[param0+0], %rd1;
+	.param .b32 retval0;
+	call.uni (retval0),
+	vprintf,
+	(
+	param0,
+	param1
+	);
+	ld.param.b32 	%r1, [retval0+0];
+	} // callseq 0
+LBB0_2:
+	ret;
+
+}
+.section .debug_str
+{
+	.b8 114,117,115,116,0
+}
+"#;
+
+    #[test]
+    fn test_tokenize() {
+        let tokens =
+            tokenize(".version 6.0\n.b8 x[2] = {1, 2};\n.entry k()\n{\nLBB0_1:\nret;\n}\n");
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Statement(".version 6.0".into(), 1),
+                Token::Statement(".b8 x[2] = {1, 2}".into(), 2),
+                Token::Open(".entry k()".into(), 3),
+                Token::Statement("LBB0_1:".into(), 5),
+                Token::Statement("ret".into(), 6),
+                Token::Close(7),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_module() {
+        let module = parse_module(SOURCE).unwrap();
+
+        assert_eq!(module.version, Some(Version { major: 6, minor: 0 }));
+        assert_eq!(module.target, vec!["sm_30"]);
+        assert_eq!(module.address_size, Some(64));
+
+        assert_eq!(
+            module.variables,
+            vec![
+                Variable {
+                    name: "$str".into(),
+                    space: StateSpace::Global,
+                    ty: ".b8".into(),
+                    linkage: None,
+                    align: Some(1),
+                    array: Some(3),
+                    line: 15,
+                },
+                Variable {
+                    name: "dynamic_shared".into(),
+                    space: StateSpace::Shared,
+                    ty: ".b8".into(),
+                    linkage: Some(".extern".into()),
+                    align: Some(4),
+                    array: Some(0),
+                    line: 16,
+                },
+                Variable {
+                    name: "WEIGHTS".into(),
+                    space: StateSpace::Const,
+                    ty: ".f32".into(),
+                    linkage: Some(".visible".into()),
+                    align: Some(4),
+                    array: Some(4),
+                    line: 17,
+                },
+            ]
+        );
+
+        let vprintf = module.function("vprintf").unwrap();
+
+        assert!(!vprintf.defined);
+        assert_eq!(vprintf.returns[0].name, "func_retval0");
+        assert_eq!(vprintf.params.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let module = parse_module(SOURCE).unwrap();
+        let entry = module.entry("example_kernel").unwrap();
+
+        assert_eq!(entry.linkage, Some(".visible".into()));
+        assert_eq!(entry.line, 19);
+
+        assert_eq!(
+            entry.params,
+            vec![
+                Param {
+                    name: "example_kernel_param_0".into(),
+                    ty: ".f64".into(),
+                    align: None,
+                    array: None,
+                },
+                Param {
+                    name: "example_kernel_param_1".into(),
+                    ty: ".b8".into(),
+                    align: Some(8),
+                    array: Some(16),
+                },
+            ]
+        );
+
+        assert_eq!(
+            entry.directives,
+            vec![("maxntid".into(), "64, 1, 1".into())]
+        );
+        assert_eq!(entry.register_count(".f64"), 3);
+        assert_eq!(entry.register_count(".b64"), 5);
+        assert_eq!(entry.variables[0].space, StateSpace::Local);
+        assert_eq!(entry.calls, vec!["vprintf"]);
+
+        let opcodes = entry
+            .instructions
+            .iter()
+            .map(|instruction| &instruction.opcode[..])
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            opcodes,
+            vec![
+                "ld.param.f64",
+                "setp.gt.f64",
+                "bra",
+                "st.param.b64",
+                "call.uni",
+                "ld.param.b32",
+                "ret"
+            ]
+        );
+
+        assert_eq!(
+            entry.instructions[0].loc,
+            Some(Loc {
+                file: 1,
+                line: 8,
+                column: 0
+            })
+        );
+
+        assert_eq!(module.file_path(1), Some("/src/main.rs"));
+        assert_eq!(entry.instructions[2].predicate, Some("!%p1".into()));
+        assert_eq!(entry.instructions[2].line, 37);
+        assert_eq!(
+            entry.instructions[4].operands,
+            vec!["(retval0)", "vprintf", "( param0, param1 )"]
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            parse_module(".version six\n"),
+            Err(InspectError::Parse {
+                line: 1,
+                reason: "malformed `.version`".into(),
+            })
+        );
+
+        assert_eq!(
+            parse_module(".entry k()\n{\nret;\n"),
+            Err(InspectError::Parse {
+                line: 1,
+                reason: "unterminated function body".into(),
+            })
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use proc_macro2::Span;
+use syn::parse::{self, Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parenthesized, token, Error, Ident, LitInt, Token};
+
+/// Maximum amount of threads in a block, for all supported architectures.
+const MAX_BLOCK_THREADS: u64 = 1024;
+
+/// Arguments of `#[kernel(max_threads = 256, min_blocks_per_sm = 2)]`.
+///
+/// Thread counts are either a number or a tuple of up to three dimensions.
+#[derive(Debug, Default, PartialEq)]
+pub struct KernelBounds {
+    pub max_threads: Option<[u32; 3]>,
+    pub min_blocks_per_sm: Option<u32>,
+    pub required_threads: Option<[u32; 3]>,
+}
+
+impl KernelBounds {
+    pub fn is_empty(&self) -> bool {
+        *self == KernelBounds::default()
+    }
+
+    /// PTX directives with their operands, e.g. `("maxntid", "256, 1, 1")`.
+    pub fn directives(&self) -> Vec<(&'static str, String)> {
+        let dims = |[x, y, z]: [u32; 3]| format!("{}, {}, {}", x, y, z);
+        let mut directives = vec![];
+
+        if let Some(max_threads) = self.max_threads {
+            directives.push(("maxntid", dims(max_threads)));
+        }
+
+        if let Some(required_threads) = self.required_threads {
+            directives.push(("reqntid", dims(required_threads)));
+        }
+
+        if let Some(min_blocks_per_sm) = self.min_blocks_per_sm {
+            directives.push(("minnctapersm", min_blocks_per_sm.to_string()));
+        }
+
+        directives
+    }
+}
+
+impl Parse for KernelBounds {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let mut bounds = KernelBounds::default();
+
+        while !input.is_empty() {
+            let name: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+
+            if name == "max_threads" {
+                set_once(&mut bounds.max_threads, parse_dims(input)?, &name)?;
+            } else if name == "required_threads" {
+                set_once(&mut bounds.required_threads, parse_dims(input)?, &name)?;
+            } else if name == "min_blocks_per_sm" {
+                set_once(
+                    &mut bounds.min_blocks_per_sm,
+                    parse_positive(&input.parse()?)?,
+                    &name,
+                )?;
+            } else {
+                return Err(Error::new(
+                    name.span(),
+                    "expected `max_threads`, `min_blocks_per_sm` or `required_threads`",
+                ));
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        if bounds.max_threads.is_some() && bounds.required_threads.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                "`max_threads` and `required_threads` can't be used together",
+            ));
+        }
+
+        Ok(bounds)
+    }
+}
+
+fn set_once<T>(slot: &mut Option<T>, value: T, name: &Ident) -> parse::Result<()> {
+    match slot.replace(value) {
+        None => Ok(()),
+        Some(_) => Err(Error::new(name.span(), format!("duplicate `{}`", name))),
+    }
+}
+
+fn parse_dims(input: ParseStream) -> parse::Result<[u32; 3]> {
+    let span = input.cursor().span();
+    let mut dims = [1; 3];
+
+    if input.peek(token::Paren) {
+        let content;
+        let paren = parenthesized!(content in input);
+        let values = Punctuated::<LitInt, Token![,]>::parse_terminated(&content)?;
+
+        if values.is_empty() || values.len() > 3 {
+            return Err(Error::new(paren.span, "expected from 1 to 3 dimensions"));
+        }
+
+        for (dim, value) in dims.iter_mut().zip(&values) {
+            *dim = parse_positive(value)?;
+        }
+    } else {
+        let value: LitInt = input.parse()?;
+        dims[0] = parse_positive(&value)?;
+    }
+
+    let threads = dims.iter().map(|&dim| u64::from(dim)).product::<u64>();
+
+    if threads > MAX_BLOCK_THREADS {
+        return Err(Error::new(
+            span,
+            format!("blocks can't have more than {} threads", MAX_BLOCK_THREADS),
+        ));
+    }
+
+    Ok(dims)
+}
+
+fn parse_positive(value: &LitInt) -> parse::Result<u32> {
+    match value.value() {
+        0 => Err(Error::new(value.span(), "the value has to be positive")),
+        value if value <= u64::from(u32::MAX) => Ok(value as u32),
+        _ => Err(Error::new(value.span(), "the value is too large")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_str;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_bounds() {
+        assert_eq!(
+            parse_str::<KernelBounds>("").unwrap(),
+            KernelBounds::default()
+        );
+
+        assert_eq!(
+            parse_str::<KernelBounds>("max_threads = 256, min_blocks_per_sm = 2").unwrap(),
+            KernelBounds {
+                max_threads: Some([256, 1, 1]),
+                min_blocks_per_sm: Some(2),
+                required_threads: None,
+            }
+        );
+
+        assert_eq!(
+            parse_str::<KernelBounds>("required_threads = (32, 8),")
+                .unwrap()
+                .directives(),
+            vec![("reqntid", "32, 8, 1".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_reject_bounds() {
+        let error = |input| parse_str::<KernelBounds>(input).unwrap_err().to_string();
+
+        assert_eq!(
+            error("max_threads = 2048"),
+            "blocks can't have more than 1024 threads"
+        );
+
+        assert_eq!(
+            error("required_threads = (32, 8, 1, 1)"),
+            "expected from 1 to 3 dimensions"
+        );
+
+        assert_eq!(error("max_threads = 0"), "the value has to be positive");
+        assert_eq!(
+            error("min_blocks_per_sm = 1, min_blocks_per_sm = 2"),
+            "duplicate `min_blocks_per_sm`"
+        );
+        assert_eq!(
+            error("max_threads = 64, required_threads = 64"),
+            "`max_threads` and `required_threads` can't be used together"
+        );
+        assert_eq!(
+            error("threads = 64"),
+            "expected `max_threads`, `min_blocks_per_sm` or `required_threads`"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use proc_macro2::{Span, TokenStream};
+use quote::*;
+
+use syn::{Ident, LitByteStr, LitStr};
+
+use crate::statics::StaticDeclaration;
+use crate::symbols::{manifest_symbol, qualify};
+
+pub fn expand_constant_static(item: StaticDeclaration) -> TokenStream {
+    let StaticDeclaration {
+        attrs,
+        vis,
+        name,
+        ty,
+    } = item;
+
+    let attrs = &attrs;
+    let name_str = name.to_string();
+    let qualified = qualify(&name);
+    let symbol = format!("__ptx_support_constant_{}", qualified);
+    let type_name = ty.clone().into_token_stream().to_string();
+
+    let declaration = LitStr::new(
+        &format!(".visible .const .align {{align}} .b8 {}[{{size}}];", symbol),
+        Span::call_site(),
+    );
+
+    let conversion = LitStr::new(
+        &format!("cvta.const.u64 {{address}}, {};", symbol),
+        Span::call_site(),
+    );
+
+    let c_name = LitByteStr::new(format!("{}\0", symbol).as_bytes(), Span::call_site());
+    let manifest_name = Ident::new(&manifest_symbol("constant", &qualified), name.span());
+
+    let manifest_fields = quote! {
+        &[
+            ::ptx_support::manifest::Field::Str("constant"),
+            ::ptx_support::manifest::Field::Str(#name_str),
+            ::ptx_support::manifest::Field::Str(#symbol),
+            ::ptx_support::manifest::Field::Str(#type_name),
+            ::ptx_support::manifest::Field::Num(::core::mem::size_of::<#ty>()),
+            ::ptx_support::manifest::Field::Num(::core::mem::align_of::<#ty>()),
+        ]
+    };
+
+    quote! {
+        #[cfg(target_os = "cuda")]
+        #(#attrs)*
+        #vis static #name: ::ptx_support::constant::Constant<#ty> = unsafe {
+            fn locate() -> *const #ty {
+                let address: u64;
+
+                unsafe {
+                    ::core::arch::asm!(#conversion, address = out(reg64) address);
+                }
+
+                address as *const #ty
+            }
+
+            ::core::arch::global_asm!(
+                #declaration,
+                align = const ::core::mem::align_of::<#ty>(),
+                size = const ::core::mem::size_of::<#ty>(),
+            );
+
+            ::ptx_support::constant::Constant::from_locator(locate)
+        };
+
+        #[cfg(target_os = "cuda")]
+        #[used]
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        static #manifest_name: [u8; ::ptx_support::manifest::encoded_len(#manifest_fields)] =
+            ::ptx_support::manifest::encode(#manifest_fields);
+
+        #[cfg(not(target_os = "cuda"))]
+        #(#attrs)*
+        #vis static #name: ::ptx_support::constant::ConstantSymbol<#ty> =
+            ::ptx_support::constant::ConstantSymbol::new(
+                unsafe { ::core::ffi::CStr::from_bytes_with_nul_unchecked(#c_name) },
+                #type_name,
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn test_host_symbol() {
+        let qualified = qualify(&Ident::new("BIASES", Span::call_site()));
+
+        let expanded = expand_constant_static(parse_quote! {
+            pub static BIASES: [f32; 4];
+        })
+        .to_string();
+
+        let symbol = format!("__ptx_support_constant_{}", qualified);
+
+        assert!(expanded.contains(&format!(
+            "static __ptx_support_manifest_constant_{}",
+            qualified
+        )));
+        assert!(expanded.contains(&format!("b\"{}\\0\"", symbol)));
+        assert!(expanded.contains(&format!(
+            "\".visible .const .align {{align}} .b8 {}[{{size}}];\"",
+            symbol
+        )));
+        assert!(expanded.contains(&format!(
+            "Str ( \"BIASES\" ) , :: ptx_support :: manifest :: Field :: Str ( \"{}\" )",
+            symbol
+        )));
+    }
+}
@@ -0,0 +1,254 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use proc_macro2::{Span, TokenStream};
+use quote::*;
+use syn::parse::{self, Parse, ParseStream};
+use syn::{Error, Ident, LitByteStr, LitStr, Macro, Token, Visibility};
+
+use ptx_support_inspect::Module;
+
+/// Arguments of `include_kernels!(pub mod kernels = env!("KERNEL_PTX_PATH"))`.
+pub struct IncludeKernels {
+    vis: Visibility,
+    name: Ident,
+    source: PtxSource,
+}
+
+enum PtxSource {
+    /// Path relative to the crate root.
+    Path(LitStr),
+
+    /// Environment variable with a path, usually set by a build script.
+    Env(LitStr),
+}
+
+impl Parse for IncludeKernels {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let vis = input.parse()?;
+        input.parse::<Token![mod]>()?;
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+
+        let source = if input.peek(LitStr) {
+            PtxSource::Path(input.parse()?)
+        } else {
+            let source: Macro = input.parse()?;
+
+            if !source.path.is_ident("env") {
+                return Err(Error::new_spanned(
+                    source.path,
+                    "expected a path literal or `env!(\"VAR\")`",
+                ));
+            }
+
+            PtxSource::Env(syn::parse2(source.tts)?)
+        };
+
+        Ok(IncludeKernels { vis, name, source })
+    }
+}
+
+impl PtxSource {
+    fn resolve(&self) -> Result<PathBuf, Error> {
+        match self {
+            PtxSource::Path(path) => {
+                let root = env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+
+                Ok(PathBuf::from(root).join(path.value()))
+            }
+
+            PtxSource::Env(name) => env::var(name.value()).map(PathBuf::from).map_err(|_| {
+                Error::new(
+                    name.span(),
+                    format!("environment variable `{}` is not set", name.value()),
+                )
+            }),
+        }
+    }
+
+    fn span(&self) -> Span {
+        match self {
+            PtxSource::Path(literal) | PtxSource::Env(literal) => literal.span(),
+        }
+    }
+}
+
+pub fn expand_include_kernels(input: IncludeKernels) -> Result<TokenStream, Vec<syn::Error>> {
+    let IncludeKernels { vis, name, source } = input;
+    let span = source.span();
+
+    let path = source.resolve().map_err(|error| vec![error])?;
+    let assembly = fs::read_to_string(&path).map_err(|error| {
+        vec![Error::new(
+            span,
+            format!("unable to read `{}`: {}", path.display(), error),
+        )]
+    })?;
+
+    if assembly.contains('\0') {
+        return Err(vec![Error::new(span, "the assembly contains a NUL byte")]);
+    }
+
+    let module = Module::parse(&assembly).map_err(|error| vec![Error::new(span, error)])?;
+
+    let mut constants: Vec<(Ident, String)> = vec![];
+
+    for entry in module.entries() {
+        let constant = Ident::new(&constant_name(&entry.name), span);
+
+        if let Some((_, other)) = constants.iter().find(|(name, _)| *name == constant) {
+            return Err(vec![Error::new(
+                span,
+                format!(
+                    "kernels `{}` and `{}` have the same constant name `{}`",
+                    other, entry.name, constant
+                ),
+            )]);
+        }
+
+        constants.push((constant, entry.name.clone()));
+    }
+
+    let path = LitStr::new(&path.to_string_lossy(), span);
+
+    let entry_consts = constants.iter().map(|(constant, entry)| {
+        let doc = format!("Name of the `{}` entry point.", entry);
+        let c_name = LitByteStr::new(format!("{}\0", entry).as_bytes(), span);
+
+        quote! {
+            #[doc = #doc]
+            pub const #constant: &::core::ffi::CStr = unsafe {
+                ::core::ffi::CStr::from_bytes_with_nul_unchecked(#c_name)
+            };
+        }
+    });
+
+    let entry_names = constants.iter().map(|(constant, _)| constant);
+
+    let env_tracking = match &source {
+        PtxSource::Env(name) => quote!(const _: &str = env!(#name);),
+        PtxSource::Path(_) => quote!(),
+    };
+
+    Ok(quote! {
+        #[allow(dead_code)]
+        #vis mod #name {
+            /// NUL-terminated PTX assembly.
+            pub static PTX: &::core::ffi::CStr = unsafe {
+                ::core::ffi::CStr::from_bytes_with_nul_unchecked(
+                    concat!(include_str!(#path), "\0").as_bytes()
+                )
+            };
+
+            /// Names of all entry points in the assembly.
+            pub const ENTRIES: &[&::core::ffi::CStr] = &[#(#entry_names),*];
+
+            #(#entry_consts)*
+
+            #env_tracking
+        }
+    })
+}
+
+/// Upper-cased entry name, with characters not allowed in identifiers replaced by `_`.
+fn constant_name(entry: &str) -> String {
+    let name: String = entry
+        .chars()
+        .map(|chr| match chr {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => chr.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect();
+
+    match name.chars().next() {
+        Some('0'..='9') => format!("_{}", name),
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use syn::parse_str;
+
+    use super::*;
+
+    fn write_assembly(name: &str, source: &str) -> String {
+        let path = env::temp_dir().join(name);
+        fs::File::create(&path)
+            .and_then(|mut file| file.write_all(source.as_bytes()))
+            .unwrap();
+
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_constant_name() {
+        assert_eq!(constant_name("example_kernel"), "EXAMPLE_KERNEL");
+        assert_eq!(constant_name("blur$1"), "BLUR_1");
+        assert_eq!(constant_name("3d_fill"), "_3D_FILL");
+    }
+
+    #[test]
+    fn test_expand_include_kernels() {
+        let path = write_assembly(
+            "ptx_support_include_kernels.ptx",
+            r#"
+.version 6.0
+.target sm_30
+.address_size 64
+
+.visible .entry example_kernel()
+{
+    ret;
+}
+
+.visible .entry other_kernel()
+{
+    ret;
+}
+"#,
+        );
+
+        let input = parse_str::<IncludeKernels>(&format!("pub mod kernels = {:?}", path)).unwrap();
+        let expanded = expand_include_kernels(input).unwrap().to_string();
+
+        assert!(expanded.contains("pub mod kernels"));
+        assert!(expanded.contains(&format!("include_str ! ( {:?} )", path)));
+        assert!(expanded.contains("pub const EXAMPLE_KERNEL"));
+        assert!(expanded.contains("pub const OTHER_KERNEL"));
+        assert!(expanded.contains(r#"b"example_kernel\0""#));
+        assert!(expanded.contains("& [ EXAMPLE_KERNEL , OTHER_KERNEL ]"));
+    }
+
+    #[test]
+    fn test_reject_include_kernels() {
+        let error = |input: &str| match parse_str::<IncludeKernels>(input) {
+            Ok(input) => expand_include_kernels(input).unwrap_err()[0].to_string(),
+            Err(error) => error.to_string(),
+        };
+
+        assert_eq!(
+            error(r#"mod kernels = include_str!("kernel.ptx")"#),
+            "expected a path literal or `env!(\"VAR\")`"
+        );
+
+        assert_eq!(
+            error(r#"mod kernels = env!("PTX_SUPPORT_MISSING_VARIABLE")"#),
+            "environment variable `PTX_SUPPORT_MISSING_VARIABLE` is not set"
+        );
+
+        let path = write_assembly(
+            "ptx_support_include_kernels_conflict.ptx",
+            ".visible .entry blur$1()\n{\n}\n.visible .entry blur_1()\n{\n}\n",
+        );
+
+        assert_eq!(
+            error(&format!("mod kernels = {:?}", path)),
+            "kernels `blur$1` and `blur_1` have the same constant name `BLUR_1`"
+        );
+    }
+}
@@ -0,0 +1,524 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::*;
+
+use syn::spanned::Spanned;
+use syn::{Error, FnArg, Ident, ItemFn, LitStr, Pat, ReturnType, Type};
+
+use crate::bounds::KernelBounds;
+use crate::symbols::manifest_symbol;
+
+pub fn expand_kernel(item: ItemFn, bounds: KernelBounds) -> Result<TokenStream, Vec<Error>> {
+    let mut errors = check_signature(&item);
+
+    let params = item
+        .decl
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Captured(arg) => Some((&arg.pat, &arg.ty)),
+
+            _ => {
+                errors.push(Error::new(
+                    input.span(),
+                    "kernel parameters have to be `name: Type` pairs",
+                ));
+
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for (_, ty) in &params {
+        if let Err(error) = check_param_type(ty) {
+            errors.push(error);
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let ItemFn {
+        attrs,
+        vis,
+        ident,
+        decl,
+        block,
+        ..
+    } = &item;
+
+    let inputs = &decl.inputs;
+    let docs = attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+    let name = LitStr::new(&ident.to_string(), ident.span());
+    let host_ident = host_struct_name(ident);
+
+    let param_asserts = params.iter().map(|(_, ty)| {
+        quote_spanned! { ty.span() =>
+            let _ = ::ptx_support::kernel::assert_kernel_param::<#ty>;
+        }
+    });
+
+    let param_signatures = params.iter().map(|(pat, ty)| {
+        let pat_name = param_name(pat);
+        let type_name = ty.into_token_stream().to_string();
+
+        quote! {
+            ::ptx_support::kernel::ParamSignature {
+                name: #pat_name,
+                ty: #type_name,
+                layout: <#ty as ::ptx_support::kernel::KernelParam>::LAYOUT,
+            }
+        }
+    });
+
+    let params_count = params.len();
+
+    let arg_names = params
+        .iter()
+        .enumerate()
+        .map(|(index, (pat, _))| match pat {
+            Pat::Ident(pat) => pat.ident.clone(),
+            pat => Ident::new(&format!("__param_{}", index), pat.span()),
+        })
+        .collect::<Vec<_>>();
+
+    let launch_params = arg_names
+        .iter()
+        .zip(&params)
+        .map(|(arg_name, (_, ty))| quote!(#arg_name: #ty))
+        .collect::<Vec<_>>();
+
+    let (arg_names, launch_params) = (&arg_names, &launch_params);
+
+    let manifest_params = params.iter().map(|(pat, ty)| {
+        let pat_name = param_name(pat);
+        let type_name = ty.into_token_stream().to_string();
+
+        quote! {
+            ::ptx_support::manifest::Field::Str(#pat_name),
+            ::ptx_support::manifest::Field::Str(#type_name),
+            ::ptx_support::manifest::Field::Num(::core::mem::size_of::<#ty>()),
+            ::ptx_support::manifest::Field::Num(::core::mem::align_of::<#ty>()),
+        }
+    });
+
+    let manifest_name = Ident::new(&manifest_symbol("kernel", &ident.to_string()), ident.span());
+
+    let manifest_fields = quote! {
+        &[
+            ::ptx_support::manifest::Field::Str("kernel"),
+            ::ptx_support::manifest::Field::Str(#name),
+            ::ptx_support::manifest::Field::Num(#params_count),
+            #(#manifest_params)*
+        ]
+    };
+
+    let bounds_manifest = match bounds.is_empty() {
+        true => quote!(),
+        false => {
+            let directives = bounds
+                .directives()
+                .into_iter()
+                .map(|(directive, operands)| {
+                    quote! {
+                        ::ptx_support::manifest::Field::Str(#directive),
+                        ::ptx_support::manifest::Field::Str(#operands),
+                    }
+                });
+
+            let manifest_name =
+                Ident::new(&manifest_symbol("bounds", &ident.to_string()), ident.span());
+
+            let manifest_fields = quote! {
+                &[
+                    ::ptx_support::manifest::Field::Str("bounds"),
+                    ::ptx_support::manifest::Field::Str(#name),
+                    #(#directives)*
+                ]
+            };
+
+            quote! {
+                #[cfg(target_os = "cuda")]
+                #[used]
+                #[no_mangle]
+                #[allow(non_upper_case_globals)]
+                static #manifest_name: [u8; ::ptx_support::manifest::encoded_len(#manifest_fields)] =
+                    ::ptx_support::manifest::encode(#manifest_fields);
+            }
+        }
+    };
+
+    let bounds_assert = bounds.required_threads.map(|dims| {
+        let [x, y, z] = dims_literals(dims);
+
+        quote! {
+            debug_assert!(
+                ::ptx_support::prelude::Context::block().dims() == (#x, #y, #z),
+                "Block dimensions don't match `required_threads` of the kernel"
+            );
+        }
+    });
+
+    let host_bounds = {
+        let max_threads = quote_dims(bounds.max_threads);
+        let required_threads = quote_dims(bounds.required_threads);
+        let min_blocks_per_sm = match bounds.min_blocks_per_sm {
+            Some(count) => {
+                let count = Literal::u32_unsuffixed(count);
+                quote!(Some(#count))
+            }
+
+            None => quote!(None),
+        };
+
+        quote! {
+            ::ptx_support::kernel::LaunchBounds {
+                max_threads: #max_threads,
+                min_blocks_per_sm: #min_blocks_per_sm,
+                required_threads: #required_threads,
+            }
+        }
+    };
+
+    let host_launch = quote! {
+        #[cfg(not(target_os = "cuda"))]
+        #[allow(dead_code)]
+        impl #host_ident {
+            /// Launches the kernel with `grid` blocks of `block` threads.
+            #[allow(clippy::too_many_arguments)]
+            pub fn launch<D: ::ptx_support::kernel::Driver>(
+                driver: &D,
+                grid: impl ::core::convert::Into<::ptx_support::kernel::Dims>,
+                block: impl ::core::convert::Into<::ptx_support::kernel::Dims>,
+                #(#launch_params),*
+            ) -> ::core::result::Result<(), D::Error> {
+                Self::launch_with(
+                    driver,
+                    ::ptx_support::kernel::LaunchConfig::new(grid, block),
+                    #(#arg_names),*
+                )
+            }
+
+            /// Launches the kernel with a complete launch configuration.
+            #[allow(clippy::too_many_arguments)]
+            pub fn launch_with<D: ::ptx_support::kernel::Driver>(
+                driver: &D,
+                config: ::ptx_support::kernel::LaunchConfig,
+                #(#launch_params),*
+            ) -> ::core::result::Result<(), D::Error> {
+                debug_assert!(
+                    <Self as ::ptx_support::kernel::Kernel>::BOUNDS.accepts(config.block),
+                    "Block dimensions don't satisfy launch bounds of the kernel"
+                );
+
+                driver.launch_kernel(#name, &config, &[
+                    #(::ptx_support::kernel::LaunchArg::new(&#arg_names)),*
+                ])
+            }
+        }
+    };
+
+    Ok(quote! {
+        #[cfg(target_os = "cuda")]
+        #[used]
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        static #manifest_name: [u8; ::ptx_support::manifest::encoded_len(#manifest_fields)] =
+            ::ptx_support::manifest::encode(#manifest_fields);
+
+        #bounds_manifest
+
+        #[cfg(target_os = "cuda")]
+        #[no_mangle]
+        #(#attrs)*
+        #vis unsafe extern "ptx-kernel" fn #ident(#(#launch_params),*) {
+            // The body is a separate safe function, so unsafe calls still need `unsafe` blocks.
+            #[inline(always)]
+            fn __kernel_body(#inputs) #block
+
+            #(#param_asserts)*
+            #bounds_assert
+
+            __kernel_body(#(#arg_names),*)
+        }
+
+        #[cfg(not(target_os = "cuda"))]
+        #(#docs)*
+        #vis struct #host_ident;
+
+        #host_launch
+
+        #[cfg(not(target_os = "cuda"))]
+        impl ::ptx_support::kernel::Kernel for #host_ident {
+            const NAME: &'static str = #name;
+            const PARAMS: &'static [::ptx_support::kernel::ParamSignature] = &[
+                #(#param_signatures),*
+            ];
+            const BOUNDS: ::ptx_support::kernel::LaunchBounds = #host_bounds;
+        }
+    })
+}
+
+fn check_signature(item: &ItemFn) -> Vec<Error> {
+    let mut errors = vec![];
+
+    if let Some(abi) = &item.abi {
+        errors.push(Error::new(
+            abi.span(),
+            "the ABI of a kernel is always \"ptx-kernel\"",
+        ));
+    }
+
+    if let Some(asyncness) = &item.asyncness {
+        errors.push(Error::new(asyncness.span(), "kernels can't be `async`"));
+    }
+
+    if let Some(constness) = &item.constness {
+        errors.push(Error::new(constness.span(), "kernels can't be `const`"));
+    }
+
+    if !item.decl.generics.params.is_empty() || item.decl.generics.where_clause.is_some() {
+        errors.push(Error::new(
+            item.decl.generics.span(),
+            "kernels can't be generic",
+        ));
+    }
+
+    if let Some(variadic) = &item.decl.variadic {
+        errors.push(Error::new(variadic.span(), "kernels can't be variadic"));
+    }
+
+    if let ReturnType::Type(_, ty) = &item.decl.output {
+        errors.push(Error::new(ty.span(), "kernels can't return values"));
+    }
+
+    errors
+}
+
+/// Rejects parameter types which are known to be not `Copy` or not FFI-safe.
+///
+/// Other types are checked by the compiler: the generated entry point asserts `KernelParam`
+/// for every parameter, and `improper_ctypes_definitions` catches the rest.
+pub fn check_param_type(ty: &Type) -> Result<(), Error> {
+    match ty {
+        Type::Reference(reference) if reference.mutability.is_some() => Err(Error::new(
+            ty.span(),
+            "`&mut` references are not `Copy`, use `DeviceSliceMut` or a raw pointer",
+        )),
+
+        Type::Reference(reference) => match &*reference.elem {
+            Type::Slice(_) => Err(Error::new(
+                ty.span(),
+                "slice references are not FFI-safe, use `DeviceSlice`",
+            )),
+
+            Type::Path(path) if path.qself.is_none() && path.path.is_ident("str") => {
+                Err(Error::new(ty.span(), "string slices are not FFI-safe"))
+            }
+
+            elem => check_param_type(elem),
+        },
+
+        Type::Slice(_) | Type::TraitObject(_) | Type::ImplTrait(_) => Err(Error::new(
+            ty.span(),
+            "dynamically sized and opaque types can't be kernel parameters",
+        )),
+
+        Type::Tuple(tuple) if !tuple.elems.is_empty() => Err(Error::new(
+            ty.span(),
+            "tuples are not FFI-safe, use a `#[repr(C)]` struct",
+        )),
+
+        Type::Path(path) if path.qself.is_none() => {
+            let last = path
+                .path
+                .segments
+                .iter()
+                .last()
+                .map(|segment| &segment.ident);
+
+            match last {
+                Some(ident) if ident == "Box" || ident == "Vec" || ident == "String" => {
+                    Err(Error::new(
+                        ty.span(),
+                        "owning heap types are neither `Copy` nor FFI-safe",
+                    ))
+                }
+
+                _ => Ok(()),
+            }
+        }
+
+        Type::Array(array) => check_param_type(&array.elem),
+        Type::Paren(paren) => check_param_type(&paren.elem),
+        Type::Group(group) => check_param_type(&group.elem),
+
+        _ => Ok(()),
+    }
+}
+
+fn dims_literals(dims: [u32; 3]) -> [Literal; 3] {
+    let [x, y, z] = dims;
+
+    [
+        Literal::u32_unsuffixed(x),
+        Literal::u32_unsuffixed(y),
+        Literal::u32_unsuffixed(z),
+    ]
+}
+
+fn quote_dims(dims: Option<[u32; 3]>) -> TokenStream {
+    match dims {
+        Some(dims) => {
+            let [x, y, z] = dims_literals(dims);
+            quote!(Some(::ptx_support::kernel::Dims { x: #x, y: #y, z: #z }))
+        }
+
+        None => quote!(None),
+    }
+}
+
+/// Name of the host-side struct: `example_kernel` becomes `ExampleKernel`,
+/// and `blur` becomes `BlurKernel`.
+fn host_struct_name(ident: &Ident) -> Ident {
+    let mut name = ident
+        .to_string()
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<String>();
+
+    if !name.ends_with("Kernel") {
+        name.push_str("Kernel");
+    }
+
+    Ident::new(&name, ident.span())
+}
+
+fn param_name(pat: &Pat) -> String {
+    match pat {
+        Pat::Ident(pat) => pat.ident.to_string(),
+        pat => pat.into_token_stream().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::Span;
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn test_check_param_type() {
+        assert!(check_param_type(&parse_quote!(f32)).is_ok());
+        assert!(check_param_type(&parse_quote!(*mut f32)).is_ok());
+        assert!(check_param_type(&parse_quote!(DeviceSlice<f32>)).is_ok());
+        assert!(check_param_type(&parse_quote!([u32; 4])).is_ok());
+
+        assert!(check_param_type(&parse_quote!(&mut f32)).is_err());
+        assert!(check_param_type(&parse_quote!(&[f32])).is_err());
+        assert!(check_param_type(&parse_quote!(&str)).is_err());
+        assert!(check_param_type(&parse_quote!((f32, f32))).is_err());
+        assert!(check_param_type(&parse_quote!(Vec<f32>)).is_err());
+        assert!(check_param_type(&parse_quote!([Box<f32>; 2])).is_err());
+    }
+
+    #[test]
+    fn test_reject_signature() {
+        let errors = expand_kernel(
+            parse_quote! {
+                pub extern "C" fn example<T>(a: T, b: &mut f32) -> f32 {
+                    0.0
+                }
+            },
+            KernelBounds::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "the ABI of a kernel is always \"ptx-kernel\"",
+                "kernels can't be generic",
+                "kernels can't return values",
+                "`&mut` references are not `Copy`, use `DeviceSliceMut` or a raw pointer",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_bounds() {
+        let item: ItemFn = parse_quote! {
+            pub fn blur(input: DeviceSlice<f32>) {}
+        };
+
+        let expanded = expand_kernel(item.clone(), KernelBounds::default())
+            .unwrap()
+            .to_string();
+
+        assert!(!expanded.contains("__ptx_support_manifest_bounds_blur"));
+        assert!(expanded.contains("const BOUNDS : :: ptx_support :: kernel :: LaunchBounds = :: ptx_support :: kernel :: LaunchBounds { max_threads : None , min_blocks_per_sm : None , required_threads : None , }"));
+
+        let bounds = KernelBounds {
+            required_threads: Some([32, 8, 1]),
+            min_blocks_per_sm: Some(2),
+            ..KernelBounds::default()
+        };
+
+        let expanded = expand_kernel(item, bounds).unwrap().to_string();
+
+        assert!(expanded.contains("static __ptx_support_manifest_bounds_blur"));
+        assert!(expanded.contains("Field :: Str ( \"reqntid\" ) , :: ptx_support :: manifest :: Field :: Str ( \"32, 8, 1\" )"));
+        assert!(expanded.contains("Context :: block ( ) . dims ( ) == ( 32 , 8 , 1 )"));
+        assert!(expanded.contains("min_blocks_per_sm : Some ( 2 )"));
+    }
+
+    #[test]
+    fn test_host_struct_name() {
+        let name = |name| host_struct_name(&Ident::new(name, Span::call_site())).to_string();
+
+        assert_eq!(name("example_kernel"), "ExampleKernel");
+        assert_eq!(name("blur"), "BlurKernel");
+        assert_eq!(name("add_2d"), "Add2dKernel");
+        assert_eq!(name("_private"), "PrivateKernel");
+    }
+
+    #[test]
+    fn test_expand_kernel() {
+        let expanded = expand_kernel(
+            parse_quote! {
+                pub fn example_kernel(a: f64, output: DeviceSliceMut<f64>) {}
+            },
+            KernelBounds::default(),
+        )
+        .unwrap()
+        .to_string();
+
+        assert!(expanded.contains(
+            "pub unsafe extern \"ptx-kernel\" fn example_kernel ( a : f64 , output : DeviceSliceMut < f64 > )"
+        ));
+        assert!(expanded.contains(
+            "# [ inline ( always ) ] fn __kernel_body ( a : f64 , output : DeviceSliceMut < f64 > ) { }"
+        ));
+        assert!(expanded.contains("__kernel_body ( a , output )"));
+        assert!(expanded.contains("pub struct ExampleKernel ;"));
+        assert!(expanded.contains("a : f64 , output : DeviceSliceMut < f64 >"));
+        assert!(expanded.contains(
+            "LaunchArg :: new ( & a ) , :: ptx_support :: kernel :: LaunchArg :: new ( & output )"
+        ));
+        assert!(expanded.contains("static __ptx_support_manifest_kernel_example_kernel"));
+        assert!(expanded.contains("const NAME : & 'static str = \"example_kernel\""));
+        assert!(expanded.contains("name : \"output\" , ty : \"DeviceSliceMut < f64 >\""));
+    }
+}
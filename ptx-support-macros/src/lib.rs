@@ -1,19 +1,34 @@
 #![deny(warnings)]
-#![feature(proc_macro_diagnostic)]
+#![feature(proc_macro_diagnostic, proc_macro_span)]
 
 extern crate proc_macro;
 use crate::proc_macro::TokenStream;
 
 use proc_macro2::Span;
 use quote::*;
-use syn::parse_macro_input;
+use syn::{parse_macro_input, DeriveInput, ItemFn};
 
 mod args;
+mod bounds;
+mod constant;
 mod error;
+mod include;
+mod kernel;
+mod param;
 mod parsers;
+mod shared;
+mod statics;
+mod symbols;
 
 use crate::args::{wrap_args, MacroInputs};
-use crate::parsers::parse_format;
+use crate::bounds::KernelBounds;
+use crate::constant::expand_constant_static;
+use crate::include::{expand_include_kernels, IncludeKernels};
+use crate::kernel::expand_kernel;
+use crate::param::expand_kernel_param;
+use crate::parsers::{host_format, parse_format, ArgType};
+use crate::shared::expand_shared_static;
+use crate::statics::StaticDeclaration;
 
 #[proc_macro]
 pub fn cuda_printf(input: TokenStream) -> TokenStream {
@@ -47,24 +62,51 @@ pub fn cuda_printf(input: TokenStream) -> TokenStream {
         }
     };
 
-    let arg_types = wrapped_args.iter().map(|item| item.inner_ty.clone());
-    let arg_names = wrapped_args.iter().map(|item| item.inner_name.clone());
+    let arg_types = &wrapped_args
+        .iter()
+        .map(|item| item.inner_ty.clone())
+        .collect::<Vec<_>>();
+
+    let arg_names = &wrapped_args
+        .iter()
+        .map(|item| item.inner_name.clone())
+        .collect::<Vec<_>>();
+
+    let arg_generics = &wrapped_args
+        .iter()
+        .map(|item| item.inner_generic.clone())
+        .filter_map(|item| item)
+        .collect::<Vec<_>>();
+
     let ffi_types = wrapped_args.iter().map(|item| item.ffi_ty.clone());
     let ffi_args = wrapped_args.iter().map(|item| item.ffi_expr.clone());
 
-    let arg_generics = wrapped_args
+    // Strings are printed directly on the host, instead of passing pointers to them.
+    let host_format = host_format(&format);
+    let host_args = parsed_format_args
         .iter()
-        .map(|item| item.inner_generic.clone())
-        .filter_map(|item| item);
+        .filter(|arg| arg.4 != ArgType::Literal)
+        .zip(&wrapped_args)
+        .map(|(arg, item)| match arg.4 {
+            ArgType::StringPointer => {
+                let name = &item.inner_name;
+                quote!(#name.as_ref())
+            }
+
+            _ => item.ffi_expr.clone(),
+        });
 
     TokenStream::from(quote! {{
+        #[cfg(target_os = "cuda")]
         extern "C" {
             pub fn vprintf(format: *const u8, valist: *const u8) -> i32;
         }
 
+        #[cfg(target_os = "cuda")]
         #[repr(C)]
         struct LocalPrintfArgs(#(#ffi_types),*);
 
+        #[cfg(target_os = "cuda")]
         fn local_typed_vprintf<#(#arg_generics),*>(format: *const u8, #(#arg_names: #arg_types),*) {
             let args = LocalPrintfArgs(#(#ffi_args),*);
 
@@ -73,6 +115,90 @@ pub fn cuda_printf(input: TokenStream) -> TokenStream {
             }
         }
 
+        #[cfg(not(target_os = "cuda"))]
+        fn local_typed_vprintf<#(#arg_generics),*>(_format: *const u8, #(#arg_names: #arg_types),*) {
+            ::std::print!(#host_format, #(#host_args),*);
+        }
+
         local_typed_vprintf(#format.as_ptr(), #(#args),*);
     }})
 }
+
+#[proc_macro_attribute]
+pub fn shared(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        Span::call_site()
+            .unstable()
+            .error("`#[shared]` doesn't take any arguments")
+            .emit();
+    }
+
+    let item = parse_macro_input!(item as StaticDeclaration);
+
+    TokenStream::from(expand_shared_static(item))
+}
+
+#[proc_macro_attribute]
+pub fn constant(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        Span::call_site()
+            .unstable()
+            .error("`#[constant]` doesn't take any arguments")
+            .emit();
+    }
+
+    let item = parse_macro_input!(item as StaticDeclaration);
+
+    TokenStream::from(expand_constant_static(item))
+}
+
+/// Turns a function into a kernel entry point.
+///
+/// On the device it generates `#[no_mangle] pub unsafe extern "ptx-kernel"` boilerplate
+/// calling the body as a safe function, and on the host a unit struct (`example_kernel` becomes `ExampleKernel`) describing
+/// the kernel signature, with typed launch functions.
+/// Crate-level attributes (`no_std`, `feature(abi_ptx)`) still have to be declared manually.
+///
+/// Optional launch bounds (`max_threads`, `min_blocks_per_sm` and `required_threads`)
+/// are recorded in the manifest and applied by `ptx_support_build::apply_launch_bounds()`.
+#[proc_macro_attribute]
+pub fn kernel(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let bounds = parse_macro_input!(attr as KernelBounds);
+    let item = parse_macro_input!(item as ItemFn);
+
+    emit_errors(expand_kernel(item, bounds))
+}
+
+/// Embeds a PTX assembly into a host module, with a constant for every kernel entry point.
+///
+/// `include_kernels!(pub mod kernels = env!("KERNEL_PTX_PATH"))` generates a NUL-terminated
+/// `kernels::PTX` and `kernels::EXAMPLE_KERNEL` for `example_kernel`, so renamed kernels
+/// are caught at compile time. Paths in literals are relative to the crate root.
+#[proc_macro]
+pub fn include_kernels(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as IncludeKernels);
+
+    emit_errors(expand_include_kernels(input))
+}
+
+/// Validates a struct for being passed by value to kernels and describes its layout.
+#[proc_macro_derive(KernelParam)]
+pub fn derive_kernel_param(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    emit_errors(expand_kernel_param(input))
+}
+
+fn emit_errors(result: Result<proc_macro2::TokenStream, Vec<syn::Error>>) -> TokenStream {
+    match result {
+        Ok(expanded) => TokenStream::from(expanded),
+
+        Err(errors) => {
+            for error in errors {
+                error.span().unstable().error(error.to_string()).emit();
+            }
+
+            TokenStream::from(quote!{})
+        }
+    }
+}
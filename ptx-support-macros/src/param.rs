@@ -0,0 +1,239 @@
+use proc_macro2::TokenStream;
+use quote::*;
+
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Ident, Index, Meta, NestedMeta, Type};
+
+use crate::kernel::check_param_type;
+use crate::symbols::{manifest_symbol, qualify};
+
+pub fn expand_kernel_param(input: DeriveInput) -> Result<TokenStream, Vec<Error>> {
+    let mut errors = vec![];
+
+    if !has_c_layout(&input.attrs) {
+        errors.push(Error::new(
+            input.ident.span(),
+            "kernel parameter structs have to be `#[repr(C)]` or `#[repr(transparent)]`",
+        ));
+    }
+
+    if !input.generics.params.is_empty() {
+        errors.push(Error::new(
+            input.generics.span(),
+            "kernel parameter structs can't be generic",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect(),
+            Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
+            Fields::Unit => vec![],
+        },
+
+        _ => {
+            errors.push(Error::new(
+                input.ident.span(),
+                "only structs can derive `KernelParam`",
+            ));
+
+            vec![]
+        }
+    };
+
+    for field in &fields {
+        if let Err(error) = check_field_type(&field.ty) {
+            errors.push(error);
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let name = &input.ident;
+    let name_str = name.to_string();
+
+    let members = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => (ident.to_string(), ident.into_token_stream()),
+            None => (index.to_string(), Index::from(index).into_token_stream()),
+        })
+        .collect::<Vec<_>>();
+
+    let field_asserts = fields.iter().map(|field| {
+        let ty = &field.ty;
+
+        quote_spanned! { ty.span() =>
+            let _ = ::ptx_support::kernel::assert_kernel_param::<#ty>;
+        }
+    });
+
+    let field_layouts = fields
+        .iter()
+        .zip(&members)
+        .map(|(field, (member_name, member))| {
+            let ty = &field.ty;
+            let type_name = ty.into_token_stream().to_string();
+
+            quote! {
+                ::ptx_support::kernel::FieldLayout {
+                    name: #member_name,
+                    ty: #type_name,
+                    offset: ::core::mem::offset_of!(#name, #member),
+                    size: ::core::mem::size_of::<#ty>(),
+                    align: ::core::mem::align_of::<#ty>(),
+                }
+            }
+        });
+
+    let manifest_offsets = members.iter().map(|(member_name, member)| {
+        quote! {
+            ::ptx_support::manifest::Field::Str(#member_name),
+            ::ptx_support::manifest::Field::Num(::core::mem::offset_of!(#name, #member)),
+        }
+    });
+
+    let qualified = qualify(name);
+    let manifest_name = Ident::new(&manifest_symbol("param", &qualified), name.span());
+
+    let manifest_fields = quote! {
+        &[
+            ::ptx_support::manifest::Field::Str("param"),
+            ::ptx_support::manifest::Field::Str(#name_str),
+            ::ptx_support::manifest::Field::Str(::core::module_path!()),
+            ::ptx_support::manifest::Field::Num(::core::mem::size_of::<#name>()),
+            ::ptx_support::manifest::Field::Num(::core::mem::align_of::<#name>()),
+            #(#manifest_offsets)*
+        ]
+    };
+
+    let copy_assert = quote_spanned! { name.span() =>
+        let _ = ::ptx_support::kernel::assert_copy::<#name>;
+    };
+
+    Ok(quote! {
+        unsafe impl ::ptx_support::kernel::KernelParam for #name {
+            const LAYOUT: ::ptx_support::kernel::ParamLayout = ::ptx_support::kernel::ParamLayout {
+                name: #name_str,
+                path: ::core::module_path!(),
+                size: ::core::mem::size_of::<#name>(),
+                align: ::core::mem::align_of::<#name>(),
+                fields: &[#(#field_layouts),*],
+            };
+        }
+
+        const _: fn() = || {
+            #copy_assert
+            #(#field_asserts)*
+        };
+
+        #[cfg(target_os = "cuda")]
+        #[used]
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        static #manifest_name: [u8; ::ptx_support::manifest::encoded_len(#manifest_fields)] =
+            ::ptx_support::manifest::encode(#manifest_fields);
+    })
+}
+
+fn has_c_layout(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("repr"))
+        .filter_map(|attr| attr.parse_meta().ok())
+        .any(|meta| match meta {
+            Meta::List(list) => list.nested.iter().any(|nested| match nested {
+                NestedMeta::Meta(Meta::Word(word)) => word == "C" || word == "transparent",
+                _ => false,
+            }),
+
+            _ => false,
+        })
+}
+
+fn check_field_type(ty: &Type) -> Result<(), Error> {
+    match ty {
+        Type::Reference(_) => Err(Error::new(
+            ty.span(),
+            "kernel parameters can't contain references, use raw pointers or device slices",
+        )),
+
+        ty => check_param_type(ty),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::Span;
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn test_reject_layout() {
+        let errors = expand_kernel_param(parse_quote! {
+            #[derive(Clone, Copy)]
+            struct Params<'a> {
+                scale: f32,
+                data: &'a f32,
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "kernel parameter structs have to be `#[repr(C)]` or `#[repr(transparent)]`",
+                "kernel parameter structs can't be generic",
+                "kernel parameters can't contain references, use raw pointers or device slices",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reject_enum() {
+        let errors = expand_kernel_param(parse_quote! {
+            #[repr(C)]
+            enum Mode {
+                Fast,
+                Precise,
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "only structs can derive `KernelParam`"
+        );
+    }
+
+    #[test]
+    fn test_expand_layout() {
+        let expanded = expand_kernel_param(parse_quote! {
+            #[repr(C, align(16))]
+            struct Params(f32, [u32; 2]);
+        })
+        .unwrap()
+        .to_string();
+
+        let qualified = qualify(&Ident::new("Params", Span::call_site()));
+
+        assert!(expanded.contains(&format!(
+            "static __ptx_support_manifest_param_{}",
+            qualified
+        )));
+        assert!(expanded.contains(
+            "Str ( \"Params\" ) , :: ptx_support :: manifest :: Field :: Str ( :: core :: module_path ! ( ) )"
+        ));
+        assert!(expanded.contains("name : \"Params\" , path : :: core :: module_path ! ( ) ,"));
+        assert!(expanded.contains("offset_of ! ( Params , 1 )"));
+        assert!(expanded.contains("name : \"1\" , ty : \"[ u32 ; 2 ]\""));
+    }
+}
@@ -25,6 +25,127 @@ pub fn parse_format(format: &str) -> Result<Vec<Arg>, PrintSyscallError> {
         })
 }
 
+/// Translates a valid `printf` format into `std::fmt` syntax, for printing on the host.
+///
+/// `*` widths and precisions are ignored, and `%g` or `%a` conversions print
+/// the shortest representation.
+pub fn host_format(format: &str) -> String {
+    let mut result = String::with_capacity(format.len());
+    let mut chars = format.chars().peekable();
+
+    while let Some(chr) = chars.next() {
+        match chr {
+            '{' => result.push_str("{{"),
+            '}' => result.push_str("}}"),
+            '%' if chars.peek() == Some(&'%') => {
+                chars.next();
+                result.push('%');
+            }
+
+            '%' => {
+                let mut flags = String::new();
+                let mut width = String::new();
+                let mut precision = None;
+
+                while let Some(&flag) = chars.peek().filter(|chr| "-+0#".contains(**chr)) {
+                    flags.push(flag);
+                    chars.next();
+                }
+
+                while let Some(&digit) = chars
+                    .peek()
+                    .filter(|chr| chr.is_ascii_digit() || **chr == '*')
+                {
+                    width.push(digit);
+                    chars.next();
+                }
+
+                if chars.peek() == Some(&'.') {
+                    chars.next();
+                    let mut digits = String::new();
+
+                    while let Some(&digit) = chars
+                        .peek()
+                        .filter(|chr| chr.is_ascii_digit() || **chr == '*')
+                    {
+                        digits.push(digit);
+                        chars.next();
+                    }
+
+                    precision = Some(digits);
+                }
+
+                while chars.peek().is_some_and(|chr| *chr == 'h' || *chr == 'l') {
+                    chars.next();
+                }
+
+                let conversion = chars.next().unwrap_or('d');
+                let width = width.replace('*', "");
+                let precision = precision.map(|digits| digits.replace('*', ""));
+
+                let mut spec = String::new();
+
+                if flags.contains('-') {
+                    spec.push('<');
+                } else if !width.is_empty() && !flags.contains('0') {
+                    spec.push('>');
+                }
+
+                if flags.contains('+') {
+                    spec.push('+');
+                }
+
+                if flags.contains('#') {
+                    spec.push('#');
+                }
+
+                if flags.contains('0') && !flags.contains('-') {
+                    spec.push('0');
+                }
+
+                spec.push_str(&width);
+
+                match (precision, conversion) {
+                    (Some(ref digits), _) if !digits.is_empty() => {
+                        spec.push('.');
+                        spec.push_str(digits);
+                    }
+
+                    (Some(_), 'f') | (Some(_), 'F') | (Some(_), 'e') | (Some(_), 'E') => {
+                        spec.push_str(".0");
+                    }
+
+                    (None, 'f') | (None, 'F') | (None, 'e') | (None, 'E') => spec.push_str(".6"),
+                    _ => {}
+                }
+
+                spec.push_str(match conversion {
+                    'x' => "x",
+                    'X' => "X",
+                    'o' => "o",
+                    'e' => "e",
+                    'E' => "E",
+                    'p' => "p",
+                    _ => "",
+                });
+
+                result.push('{');
+
+                if !spec.is_empty() {
+                    result.push(':');
+                    result.push_str(&spec);
+                }
+
+                result.push('}');
+            }
+
+            chr => result.push(chr),
+        }
+    }
+
+    result
+}
+
 // Parsing based on:
 // https://en.wikipedia.org/wiki/Printf_format_string
 
@@ -119,6 +240,30 @@ mod tests {
     use nom::Err::*;
     use nom::ErrorKind::*;
 
+    #[test]
+    fn test_host_format() {
+        assert_eq!(host_format("plain {text}\n"), "plain {{text}}\n");
+        assert_eq!(host_format("100%% of %d"), "100% of {}");
+        assert_eq!(
+            host_format("%lu %5u %-5s %05i %+d"),
+            "{} {:>5} {:<5} {:05} {:+}"
+        );
+        assert_eq!(
+            host_format("%f %.2f %8.3e %.f %g"),
+            "{:.6} {:.2} {:>8.3e} {:.0} {}"
+        );
+        assert_eq!(host_format("%x %#X %p %c %.3s"), "{:x} {:#X} {:p} {} {:.3}");
+
+        assert_eq!(
+            host_format("thread %lu of %lu: %s %5.2f\n"),
+            "thread {} of {}: {} {:>5.2}\n"
+        );
+        assert_eq!(
+            format!("thread {} of {}: {} {:>5.2}\n", 1, 2, "value", 1.5),
+            "thread 1 of 2: value  1.50\n"
+        );
+    }
+
     #[test]
     fn test_arg_flag() {
         assert_eq!(arg_flag(CompleteStr("-")), Ok((CompleteStr(""), ArgFlag)));
@@ -0,0 +1,77 @@
+use proc_macro2::{Span, TokenStream};
+use quote::*;
+
+use syn::{Ident, LitStr};
+
+use crate::statics::StaticDeclaration;
+use crate::symbols::qualify;
+
+pub fn symbol_name(name: &Ident) -> String {
+    format!("__ptx_support_shared_{}", qualify(name))
+}
+
+pub fn expand_shared_static(item: StaticDeclaration) -> TokenStream {
+    let StaticDeclaration {
+        attrs,
+        vis,
+        name,
+        ty,
+    } = item;
+
+    let symbol = symbol_name(&name);
+
+    let declaration = LitStr::new(
+        &format!(".shared .align {{align}} .b8 {}[{{size}}];", symbol),
+        Span::call_site(),
+    );
+
+    let conversion = LitStr::new(
+        &format!("cvta.shared.u64 {{address}}, {};", symbol),
+        Span::call_site(),
+    );
+
+    quote! {
+        #(#attrs)*
+        #vis static #name: ::ptx_support::shared::Shared<#ty> = unsafe {
+            fn locate() -> *mut #ty {
+                let address: u64;
+
+                unsafe {
+                    ::core::arch::asm!(#conversion, address = out(reg64) address);
+                }
+
+                address as *mut #ty
+            }
+
+            ::core::arch::global_asm!(
+                #declaration,
+                align = const ::core::mem::align_of::<#ty>(),
+                size = const ::core::mem::size_of::<#ty>(),
+            );
+
+            ::ptx_support::shared::Shared::from_locator(locate)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn test_symbol_name() {
+        let symbol = symbol_name(&Ident::new("TILE", Span::call_site()));
+
+        assert!(symbol.starts_with("__ptx_support_shared_ptx_support_macros_TILE_"));
+
+        let expanded = expand_shared_static(parse_quote! {
+            static TILE: [f32; 256];
+        })
+        .to_string();
+
+        assert!(expanded.contains(&format!(".b8 {}[{{size}}];", symbol)));
+        assert!(expanded.contains(&format!("cvta.shared.u64 {{address}}, {};", symbol)));
+    }
+}
@@ -0,0 +1,82 @@
+use syn::parse::{self, Parse, ParseStream};
+use syn::{Attribute, Ident, Token, Type, Visibility};
+
+/// Declaration of a `static` which memory is provided by the device: `static NAME: Type;`.
+pub struct StaticDeclaration {
+    pub attrs: Vec<Attribute>,
+    pub vis: Visibility,
+    pub name: Ident,
+    pub ty: Type,
+}
+
+impl Parse for StaticDeclaration {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+
+        input.parse::<Token![static]>()?;
+
+        if input.peek(Token![mut]) {
+            return Err(
+                input.error("the memory is only accessible through immutable `static` handles")
+            );
+        }
+
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+
+        if input.peek(Token![=]) {
+            return Err(input.error("the memory can't be initialized by an expression"));
+        }
+
+        input.parse::<Token![;]>()?;
+
+        Ok(StaticDeclaration {
+            attrs,
+            vis,
+            name,
+            ty,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::*;
+    use syn::{parse_quote, parse_str};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_declaration() {
+        let item: StaticDeclaration = parse_quote! {
+            #[doc = "tile"]
+            pub static TILE: SharedArray<f32, 256>;
+        };
+
+        assert_eq!(item.attrs.len(), 1);
+        assert_eq!(item.name.to_string(), "TILE");
+        assert_eq!(
+            item.ty.into_token_stream().to_string(),
+            "SharedArray < f32 , 256 >"
+        );
+    }
+
+    #[test]
+    fn test_reject_initializer() {
+        assert_eq!(
+            parse_str::<StaticDeclaration>("static TILE: SharedArray<f32, 256> = 0;")
+                .err()
+                .map(|error| error.to_string()),
+            Some("the memory can't be initialized by an expression".into())
+        );
+
+        assert_eq!(
+            parse_str::<StaticDeclaration>("static mut TILE: SharedArray<f32, 256>;")
+                .err()
+                .map(|error| error.to_string()),
+            Some("the memory is only accessible through immutable `static` handles".into())
+        );
+    }
+}
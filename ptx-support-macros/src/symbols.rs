@@ -0,0 +1,105 @@
+use std::env;
+use std::path::Path;
+
+use syn::Ident;
+
+pub fn manifest_symbol(kind: &str, name: &str) -> String {
+    format!("__ptx_support_manifest_{}_{}", kind, name)
+}
+
+/// Qualifies a declared name with the crate name and a hash of the declaration site,
+/// since symbols of the PTX assembly share a single namespace.
+///
+/// Proc macros can't see the module path, so the source file relative to the crate root,
+/// the position of the name and of the macro invocations producing it stand in for it.
+/// The result only depends on the source code, so it's the same for the host and the device.
+pub fn qualify(name: &Ident) -> String {
+    qualify_site(
+        &env::var("CARGO_PKG_NAME").unwrap_or_default(),
+        &declaration_site(name),
+        &name.to_string(),
+    )
+}
+
+pub fn qualify_site(crate_name: &str, site: &str, name: &str) -> String {
+    let crate_name = crate_name
+        .chars()
+        .map(|chr| {
+            if chr.is_ascii_alphanumeric() {
+                chr
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    format!("{}_{}_{:016x}", crate_name, name, fnv1a(site.as_bytes()))
+}
+
+/// Positions of the name and of all macro invocations it came from, e.g. `src/conv.rs:12:11`.
+///
+/// Unit tests don't run inside of a proc macro, so all names there have the same empty site.
+fn declaration_site(name: &Ident) -> String {
+    if !proc_macro::is_available() {
+        return String::new();
+    }
+
+    let crate_root = env::var("CARGO_MANIFEST_DIR")
+        .ok()
+        .and_then(|path| Path::new(&path).canonicalize().ok());
+
+    let mut sites = vec![];
+    let mut span = Some(name.span().unstable());
+
+    while let Some(current) = span {
+        // Paths are relative to the working directory of cargo, which differs between builds.
+        let file = current
+            .local_file()
+            .and_then(|path| env::current_dir().ok()?.join(path).canonicalize().ok())
+            .and_then(|path| Some(path.strip_prefix(crate_root.as_ref()?).ok()?.to_owned()))
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|| current.file());
+
+        sites.push(format!("{}:{}:{}", file, current.line(), current.column()));
+        span = current.parent();
+    }
+
+    sites.join(";")
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_symbol() {
+        assert_eq!(
+            manifest_symbol("constant", "WEIGHTS"),
+            "__ptx_support_manifest_constant_WEIGHTS"
+        );
+    }
+
+    #[test]
+    fn test_qualify_site() {
+        assert_eq!(
+            qualify_site("cuda-kernels", "", "WEIGHTS"),
+            "cuda_kernels_WEIGHTS_cbf29ce484222325"
+        );
+
+        assert_eq!(
+            qualify_site("cuda-kernels", "src/conv.rs:12:11", "WEIGHTS"),
+            qualify_site("cuda-kernels", "src/conv.rs:12:11", "WEIGHTS")
+        );
+
+        assert_ne!(
+            qualify_site("cuda-kernels", "src/conv.rs:12:11", "WEIGHTS"),
+            qualify_site("cuda-kernels", "src/blur.rs:12:11", "WEIGHTS")
+        );
+    }
+}
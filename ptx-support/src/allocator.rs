@@ -0,0 +1,67 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::arch::nvptx::*;
+use core::ptr::null_mut;
+
+/// Alignment guaranteed by the device-side `malloc`.
+const MALLOC_ALIGNMENT: usize = 16;
+
+/// Global allocator backed by the device-side `malloc` and `free` syscalls.
+///
+/// The allocations come from the device heap, which size is configured by the host
+/// with `cuCtxSetLimit(CU_LIMIT_MALLOC_HEAP_SIZE, ...)`.
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: CudaAllocator = CudaAllocator;
+/// ```
+pub struct CudaAllocator;
+
+unsafe impl GlobalAlloc for CudaAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.align() <= MALLOC_ALIGNMENT {
+            return malloc(layout.size()) as *mut u8;
+        }
+
+        // Over-allocate and keep the original pointer right before the aligned block.
+        let raw = match layout.size().checked_add(layout.align()) {
+            Some(size) => malloc(size) as *mut u8,
+            None => return null_mut(),
+        };
+
+        if raw.is_null() {
+            return raw;
+        }
+
+        let aligned = raw.add(layout.align() - (raw as usize & (layout.align() - 1)));
+        (aligned as *mut *mut u8).sub(1).write(raw);
+
+        aligned
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if layout.align() <= MALLOC_ALIGNMENT {
+            free(ptr as _);
+        } else {
+            free((ptr as *mut *mut u8).sub(1).read() as _);
+        }
+    }
+}
+
+#[alloc_error_handler]
+fn alloc_error(layout: Layout) -> ! {
+    #[cfg(feature = "macros")]
+    {
+        use crate::prelude::*;
+
+        cuda_printf!(
+            "Unable to allocate %lu bytes aligned to %lu on the device heap.\n",
+            layout.size() as u64,
+            layout.align() as u64,
+        );
+    }
+
+    #[cfg(not(feature = "macros"))]
+    let _ = layout;
+
+    panic!("Device memory allocation failed");
+}
@@ -0,0 +1,606 @@
+//! Block-wide collectives in the spirit of CUB.
+//!
+//! Every collective needs a temporary storage which has to be shared by all threads of the block,
+//! so the storage must be placed in shared memory by the caller:
+//!
+//! ```ignore
+//! #[shared]
+//! static STORAGE: ReduceStorage<f32>;
+//!
+//! let sum = BlockReduce::new(&STORAGE).sum(value);
+//! ```
+//! Collectives have to be called by all threads of the block, and the block size must be
+//! a multiple of the warp size.
+
+use core::cell::UnsafeCell;
+use core::iter::StepBy;
+use core::mem::MaybeUninit;
+use core::ops::Range;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::context::{Block, Context};
+#[cfg(target_os = "cuda")]
+use crate::shared::SharedMemory;
+use crate::thread_group::{ThreadGroup, WARP_SIZE};
+use crate::warp::{self, Shuffle, Sum, WarpOp};
+
+const MAX_WARPS: usize = 32;
+
+/// Fixed amount of uninitialized slots, accessed by several threads.
+struct Slots<T, const N: usize>(UnsafeCell<MaybeUninit<[T; N]>>);
+
+/// Temporary storage for [`BlockReduce`].
+pub struct ReduceStorage<T> {
+    warp_aggregates: Slots<T, MAX_WARPS>,
+}
+
+/// Temporary storage for [`BlockScan`].
+pub struct ScanStorage<T> {
+    warp_aggregates: Slots<T, MAX_WARPS>,
+}
+
+/// Temporary storage for [`BlockHistogram`] with `BINS` bins.
+pub struct HistogramStorage<const BINS: usize> {
+    bins: Slots<u32, BINS>,
+}
+
+/// Temporary storage for [`BlockRadixSort`] of a block with `THREADS` threads.
+pub struct RadixSortStorage<K, const THREADS: usize> {
+    keys: Slots<K, THREADS>,
+    scan: ScanStorage<u32>,
+}
+
+/// Reduction of one value per thread.
+pub struct BlockReduce<'a, T> {
+    storage: &'a ReduceStorage<T>,
+}
+
+/// Prefix scan of one value per thread, in order of thread ranks.
+pub struct BlockScan<'a, T> {
+    storage: &'a ScanStorage<T>,
+}
+
+/// Histogram of samples contributed by all threads.
+pub struct BlockHistogram<'a, const BINS: usize> {
+    storage: &'a HistogramStorage<BINS>,
+}
+
+/// Sort of one key per thread, in order of thread ranks.
+pub struct BlockRadixSort<'a, K, const THREADS: usize> {
+    storage: &'a RadixSortStorage<K, THREADS>,
+}
+
+/// Keys that can be ordered by their bit representation.
+pub trait RadixKey: Copy {
+    const BITS: u32;
+
+    /// Bits which compare as unsigned integers in the same order as keys.
+    fn to_radix(self) -> u64;
+}
+
+impl<T, const N: usize> Slots<T, N> {
+    const fn uninit() -> Self {
+        Slots(UnsafeCell::new(MaybeUninit::uninit()))
+    }
+
+    #[inline(always)]
+    fn slot(&self, index: usize) -> *mut T {
+        assert!(index < N);
+
+        unsafe { (self.0.get() as *mut T).add(index) }
+    }
+
+    #[inline(always)]
+    unsafe fn write(&self, index: usize, value: T) {
+        self.slot(index).write(value);
+    }
+
+    #[inline(always)]
+    unsafe fn read(&self, index: usize) -> T
+    where
+        T: Copy,
+    {
+        self.slot(index).read()
+    }
+}
+
+impl<T> ReduceStorage<T> {
+    /// Creates uninitialized storage.
+    ///
+    /// # Safety
+    /// The storage has to be placed in shared memory, or otherwise be used by a single block at a time.
+    pub const unsafe fn uninit() -> Self {
+        ReduceStorage {
+            warp_aggregates: Slots::uninit(),
+        }
+    }
+}
+
+impl<T> ScanStorage<T> {
+    /// Creates uninitialized storage.
+    ///
+    /// # Safety
+    /// The storage has to be placed in shared memory, or otherwise be used by a single block at a time.
+    pub const unsafe fn uninit() -> Self {
+        ScanStorage {
+            warp_aggregates: Slots::uninit(),
+        }
+    }
+}
+
+impl<const BINS: usize> HistogramStorage<BINS> {
+    /// Creates uninitialized storage.
+    ///
+    /// # Safety
+    /// The storage has to be placed in shared memory, or otherwise be used by a single block at a time.
+    pub const unsafe fn uninit() -> Self {
+        HistogramStorage {
+            bins: Slots::uninit(),
+        }
+    }
+}
+
+impl<K, const THREADS: usize> RadixSortStorage<K, THREADS> {
+    /// Creates uninitialized storage.
+    ///
+    /// # Safety
+    /// The storage has to be placed in shared memory, or otherwise be used by a single block at a time.
+    pub const unsafe fn uninit() -> Self {
+        RadixSortStorage {
+            keys: Slots::uninit(),
+            scan: ScanStorage::uninit(),
+        }
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Sync for Slots<T, N> {}
+
+#[cfg(target_os = "cuda")]
+unsafe impl<T: Send> SharedMemory for ReduceStorage<T> {}
+#[cfg(target_os = "cuda")]
+unsafe impl<T: Send> SharedMemory for ScanStorage<T> {}
+#[cfg(target_os = "cuda")]
+unsafe impl<const BINS: usize> SharedMemory for HistogramStorage<BINS> {}
+#[cfg(target_os = "cuda")]
+unsafe impl<K: Send, const THREADS: usize> SharedMemory for RadixSortStorage<K, THREADS> {}
+
+impl<'a, T: Shuffle> BlockReduce<'a, T> {
+    pub fn new(storage: &'a ReduceStorage<T>) -> Self {
+        BlockReduce { storage }
+    }
+
+    /// Reduces values of all threads with `op`. Every thread receives the result.
+    pub fn reduce<O: WarpOp<T> + Copy>(&self, value: T, op: O) -> T {
+        let block = Context::block();
+        let (warp_id, lane) = warp_position(&block);
+
+        let warp_aggregate = warp::reduce(value, op);
+
+        if lane == 0 {
+            unsafe { self.storage.warp_aggregates.write(warp_id, warp_aggregate) };
+        }
+
+        block.sync();
+
+        let mut result = unsafe { self.storage.warp_aggregates.read(0) };
+
+        for warp in 1..warps_count(&block) {
+            result = op.combine(result, unsafe { self.storage.warp_aggregates.read(warp) });
+        }
+
+        block.sync();
+        result
+    }
+
+    pub fn sum(&self, value: T) -> T
+    where
+        Sum: WarpOp<T>,
+    {
+        self.reduce(value, Sum)
+    }
+}
+
+impl<'a, T: Shuffle> BlockScan<'a, T> {
+    pub fn new(storage: &'a ScanStorage<T>) -> Self {
+        BlockScan { storage }
+    }
+
+    /// Computes inclusive prefix of `op` over thread ranks.
+    pub fn inclusive_scan<O: WarpOp<T> + Copy>(&self, value: T, op: O) -> T {
+        let block = Context::block();
+        let (warp_id, _) = warp_position(&block);
+
+        let warp_inclusive = self.publish_warp_aggregate(&block, value, op);
+        let result = inclusive_result(
+            self.fold_warp_aggregates(0..warp_id, op),
+            warp_inclusive,
+            op,
+        );
+
+        block.sync();
+        result
+    }
+
+    /// Computes exclusive prefix of `op` over thread ranks, seeded with `initial`.
+    pub fn exclusive_scan<O: WarpOp<T> + Copy>(&self, value: T, initial: T, op: O) -> T {
+        self.exclusive_scan_with_aggregate(value, initial, op).0
+    }
+
+    pub fn inclusive_sum(&self, value: T) -> T
+    where
+        Sum: WarpOp<T>,
+    {
+        self.inclusive_scan(value, Sum)
+    }
+
+    pub fn exclusive_sum(&self, value: T, zero: T) -> T
+    where
+        Sum: WarpOp<T>,
+    {
+        self.exclusive_scan(value, zero, Sum)
+    }
+
+    /// Computes exclusive prefix together with the aggregate of the whole block.
+    pub fn exclusive_scan_with_aggregate<O: WarpOp<T> + Copy>(
+        &self,
+        value: T,
+        initial: T,
+        op: O,
+    ) -> (T, T) {
+        let block = Context::block();
+        let (warp_id, lane) = warp_position(&block);
+
+        let warp_inclusive = self.publish_warp_aggregate(&block, value, op);
+        let warp_exclusive = warp_inclusive.shfl_up(1);
+
+        let result = exclusive_result(
+            initial,
+            self.fold_warp_aggregates(0..warp_id, op),
+            lane,
+            warp_exclusive,
+            op,
+        );
+
+        let aggregate = self
+            .fold_warp_aggregates(0..warps_count(&block), op)
+            .unwrap_or(initial);
+
+        block.sync();
+        (result, aggregate)
+    }
+
+    fn publish_warp_aggregate<O: WarpOp<T> + Copy>(&self, block: &Block, value: T, op: O) -> T {
+        let (warp_id, lane) = warp_position(block);
+        let warp_inclusive = warp::inclusive_scan(value, op);
+
+        if lane == WARP_SIZE as usize - 1 {
+            unsafe { self.storage.warp_aggregates.write(warp_id, warp_inclusive) };
+        }
+
+        block.sync();
+        warp_inclusive
+    }
+
+    fn fold_warp_aggregates<O: WarpOp<T>>(&self, warps: Range<usize>, op: O) -> Option<T> {
+        warps
+            .map(|warp| unsafe { self.storage.warp_aggregates.read(warp) })
+            .fold(None, |prefix, aggregate| match prefix {
+                Some(prefix) => Some(op.combine(prefix, aggregate)),
+                None => Some(aggregate),
+            })
+    }
+}
+
+impl<'a, const BINS: usize> BlockHistogram<'a, BINS> {
+    pub fn new(storage: &'a HistogramStorage<BINS>) -> Self {
+        BlockHistogram { storage }
+    }
+
+    /// Counts `samples` of every thread into bins.
+    ///
+    /// Afterwards `flush` is called for every bin exactly once within the block,
+    /// with bins distributed between threads, so the counts can be written out cooperatively.
+    pub fn histogram<I, F>(&self, samples: I, mut flush: F)
+    where
+        I: IntoIterator<Item = usize>,
+        F: FnMut(usize, u32),
+    {
+        let block = Context::block();
+        let rank = block.thread_rank() as usize;
+        let stride = block.size() as usize;
+
+        for bin in thread_bins(rank, stride, BINS) {
+            unsafe { self.storage.bins.write(bin, 0) };
+        }
+
+        block.sync();
+
+        for sample in samples {
+            self.bin(sample).fetch_add(1, Ordering::Relaxed);
+        }
+
+        block.sync();
+
+        for bin in thread_bins(rank, stride, BINS) {
+            flush(bin, self.bin(bin).load(Ordering::Relaxed));
+        }
+
+        block.sync();
+    }
+
+    fn bin(&self, index: usize) -> &AtomicU32 {
+        unsafe { AtomicU32::from_ptr(self.storage.bins.slot(index)) }
+    }
+}
+
+impl<'a, K: RadixKey, const THREADS: usize> BlockRadixSort<'a, K, THREADS> {
+    pub fn new(storage: &'a RadixSortStorage<K, THREADS>) -> Self {
+        BlockRadixSort { storage }
+    }
+
+    /// Sorts keys of all threads in ascending order.
+    /// The thread with rank `i` receives the `i`-th smallest key.
+    pub fn sort(&self, key: K) -> K {
+        self.sort_bits(key, 0, K::BITS)
+    }
+
+    /// Sorts keys by the bits in range `begin_bit..end_bit` of their radix representation.
+    pub fn sort_bits(&self, key: K, begin_bit: u32, end_bit: u32) -> K {
+        let block = Context::block();
+        let rank = block.thread_rank() as usize;
+        let scan = BlockScan::new(&self.storage.scan);
+
+        assert_eq!(block.size() as usize, THREADS);
+
+        let mut key = key;
+
+        for bit in begin_bit..end_bit.min(K::BITS) {
+            let is_set = radix_bit(key, bit);
+            let (zeros_before, zeros) =
+                scan.exclusive_scan_with_aggregate(if is_set { 0 } else { 1 }, 0, Sum);
+
+            let destination = radix_destination(is_set, rank, zeros_before, zeros);
+
+            unsafe { self.storage.keys.write(destination, key) };
+            block.sync();
+
+            key = unsafe { self.storage.keys.read(rank) };
+            block.sync();
+        }
+
+        key
+    }
+}
+
+macro_rules! impl_radix_key {
+    ($($ty:ty => |$key:ident| $radix:expr,)+) => {$(
+        impl RadixKey for $ty {
+            const BITS: u32 = (core::mem::size_of::<$ty>() * 8) as u32;
+
+            #[inline(always)]
+            fn to_radix(self) -> u64 {
+                let $key = self;
+                $radix
+            }
+        }
+    )+};
+}
+
+impl_radix_key! {
+    u32 => |key| u64::from(key),
+    u64 => |key| key,
+    i32 => |key| u64::from(key as u32 ^ (1 << 31)),
+    i64 => |key| key as u64 ^ (1 << 63),
+    f32 => |key| u64::from(match key.to_bits() {
+        bits if bits >> 31 == 1 => !bits,
+        bits => bits | (1 << 31),
+    }),
+    f64 => |key| match key.to_bits() {
+        bits if bits >> 63 == 1 => !bits,
+        bits => bits | (1 << 63),
+    },
+}
+
+/// Inclusive scan result of a thread, from the aggregate of preceding warps, if any.
+fn inclusive_result<T, O: WarpOp<T>>(preceding_warps: Option<T>, warp_inclusive: T, op: O) -> T {
+    match preceding_warps {
+        Some(prefix) => op.combine(prefix, warp_inclusive),
+        None => warp_inclusive,
+    }
+}
+
+/// Exclusive scan result of a thread, where `warp_exclusive` is the inclusive value
+/// of the previous lane, unless the thread is the first lane of its warp.
+fn exclusive_result<T, O: WarpOp<T>>(
+    initial: T,
+    preceding_warps: Option<T>,
+    lane: usize,
+    warp_exclusive: T,
+    op: O,
+) -> T {
+    let prefix = match preceding_warps {
+        Some(prefix) => op.combine(initial, prefix),
+        None => initial,
+    };
+
+    match lane {
+        0 => prefix,
+        _ => op.combine(prefix, warp_exclusive),
+    }
+}
+
+/// Bins of a histogram initialized and flushed by the thread with rank `rank`.
+fn thread_bins(rank: usize, stride: usize, bins: usize) -> StepBy<Range<usize>> {
+    (rank..bins).step_by(stride)
+}
+
+fn radix_bit<K: RadixKey>(key: K, bit: u32) -> bool {
+    (key.to_radix() >> bit) & 1 == 1
+}
+
+/// Position of a key after a radix sort pass: keys with the bit unset keep their order
+/// at the beginning, keys with the bit set keep their order after all of them.
+fn radix_destination(is_set: bool, rank: usize, zeros_before: u32, zeros: u32) -> usize {
+    match is_set {
+        true => zeros as usize + rank - zeros_before as usize,
+        false => zeros_before as usize,
+    }
+}
+
+fn warp_position(block: &Block) -> (usize, usize) {
+    let rank = block.thread_rank();
+
+    ((rank / WARP_SIZE) as usize, (rank % WARP_SIZE) as usize)
+}
+
+fn warps_count(block: &Block) -> usize {
+    block.size().div_ceil(WARP_SIZE) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    use super::*;
+
+    fn concat(a: String, b: String) -> String {
+        a + &b
+    }
+
+    #[test]
+    fn test_scan_boundaries() {
+        let s = |value: &str| value.to_string();
+
+        assert_eq!(inclusive_result(None, s("ab"), concat), "ab");
+        assert_eq!(inclusive_result(Some(s("xy")), s("ab"), concat), "xyab");
+
+        assert_eq!(exclusive_result(s("i"), None, 0, s("-"), concat), "i");
+        assert_eq!(exclusive_result(s("i"), None, 3, s("abc"), concat), "iabc");
+        assert_eq!(
+            exclusive_result(s("i"), Some(s("p")), 0, s("-"), concat),
+            "ip"
+        );
+        assert_eq!(
+            exclusive_result(s("i"), Some(s("p")), 5, s("ab"), concat),
+            "ipab"
+        );
+    }
+
+    #[test]
+    fn test_block_scan_results() {
+        let warp_size = WARP_SIZE as usize;
+        let values = (0..3 * warp_size)
+            .map(|rank| rank as u32 % 7)
+            .collect::<Vec<_>>();
+
+        let warp_inclusive = values
+            .chunks(warp_size)
+            .flat_map(|warp| {
+                warp.iter().scan(0, |sum, value| {
+                    *sum += value;
+                    Some(*sum)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let aggregates = values
+            .chunks(warp_size)
+            .map(|warp| warp.iter().sum::<u32>())
+            .collect::<Vec<_>>();
+
+        for rank in 0..values.len() {
+            let (warp, lane) = (rank / warp_size, rank % warp_size);
+            let preceding_warps = match warp {
+                0 => None,
+                _ => Some(aggregates[..warp].iter().sum()),
+            };
+
+            let preceding = values[..rank].iter().sum::<u32>();
+            let add = |a: u32, b: u32| a + b;
+
+            assert_eq!(
+                inclusive_result(preceding_warps, warp_inclusive[rank], add),
+                preceding + values[rank]
+            );
+
+            let warp_exclusive = warp_inclusive[rank.saturating_sub(1)];
+
+            assert_eq!(
+                exclusive_result(10, preceding_warps, lane, warp_exclusive, add),
+                10 + preceding
+            );
+        }
+    }
+
+    #[test]
+    fn test_thread_bins() {
+        for &(stride, bins) in &[(4, 10), (32, 10), (3, 3), (5, 0)] {
+            let mut assigned = (0..stride)
+                .flat_map(|rank| thread_bins(rank, stride, bins))
+                .collect::<Vec<_>>();
+
+            assigned.sort();
+            assert_eq!(assigned, (0..bins).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_radix_keys() {
+        assert!(radix_bit(5u32, 0) && !radix_bit(5u32, 1) && radix_bit(5u32, 2));
+        assert!(radix_bit(0i32, 31) && !radix_bit(-1i32, 31));
+
+        fn ascending<K: RadixKey>(keys: &[K]) -> bool {
+            keys.windows(2)
+                .all(|pair| pair[0].to_radix() < pair[1].to_radix())
+        }
+
+        assert!(ascending(&[i32::MIN, -1, 0, 1, i32::MAX]));
+        assert!(ascending(&[i64::MIN, -1, 0, 1, i64::MAX]));
+        assert!(ascending(&[
+            f32::NEG_INFINITY,
+            -2.5,
+            -0.0,
+            0.0,
+            1e-30,
+            3.0,
+            f32::INFINITY
+        ]));
+        assert!(ascending(&[f64::NEG_INFINITY, -1e300, -0.0, 0.0, 2.0]));
+    }
+
+    #[test]
+    fn test_radix_destination() {
+        let destinations = [true, false, true, false]
+            .iter()
+            .enumerate()
+            .scan(0, |zeros_before, (rank, &is_set)| {
+                let destination = radix_destination(is_set, rank, *zeros_before, 2);
+                *zeros_before += u32::from(!is_set);
+                Some(destination)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(destinations, [2, 0, 3, 1]);
+
+        // Sorts like the block does, with one key per thread rank.
+        let mut keys = [5, -3, 12, 0, -3, 7, i32::MIN, 2, i32::MAX, -40];
+
+        for bit in 0..i32::BITS {
+            let zeros = keys.iter().filter(|key| !radix_bit(**key, bit)).count() as u32;
+            let mut sorted = [0; 10];
+            let mut zeros_before = 0;
+
+            for (rank, &key) in keys.iter().enumerate() {
+                let is_set = radix_bit(key, bit);
+
+                sorted[radix_destination(is_set, rank, zeros_before, zeros)] = key;
+                zeros_before += u32::from(!is_set);
+            }
+
+            keys = sorted;
+        }
+
+        assert_eq!(keys, [i32::MIN, -40, -3, -3, 0, 2, 5, 7, 12, i32::MAX]);
+    }
+}
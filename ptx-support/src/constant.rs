@@ -0,0 +1,97 @@
+//! Statics placed in the constant memory space with the `#[constant]` attribute.
+//!
+//! ```ignore
+//! #[constant]
+//! static WEIGHTS: [f32; 256];
+//! ```
+//!
+//! On the device the static is a [`Constant`] handle which dereferences to the value.
+//! On the host the same declaration turns into a [`ConstantSymbol`], which knows the symbol name
+//! for `cuModuleGetGlobal` and only accepts values of the declared type for uploading.
+//!
+//! Symbol names are qualified with the crate name and a hash of the declaration site, e.g.
+//! `__ptx_support_constant_kernels_WEIGHTS_5e1a0c3b9d2f4e67`, so constants of different
+//! modules don't clash. The hash only depends on the source, so host and device builds agree.
+
+#[cfg(target_os = "cuda")]
+use core::ops::Deref;
+
+#[cfg(not(target_os = "cuda"))]
+use core::{ffi::CStr, marker::PhantomData, mem::size_of, slice};
+
+/// Device-side handle to a constant memory variable.
+#[cfg(target_os = "cuda")]
+pub struct Constant<T: Copy> {
+    locate: fn() -> *const T,
+}
+
+/// Host-side description of a constant memory variable.
+#[cfg(not(target_os = "cuda"))]
+pub struct ConstantSymbol<T: Copy> {
+    name: &'static CStr,
+    type_name: &'static str,
+    _marker: PhantomData<fn(T)>,
+}
+
+#[cfg(target_os = "cuda")]
+impl<T: Copy> Constant<T> {
+    /// Creates a handle from a function returning the generic address of the variable.
+    ///
+    /// # Safety
+    /// `locate` has to return a properly aligned pointer into the constant memory space.
+    pub const unsafe fn from_locator(locate: fn() -> *const T) -> Self {
+        Constant { locate }
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl<T: Copy> Deref for Constant<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*(self.locate)() }
+    }
+}
+
+#[cfg(target_os = "cuda")]
+unsafe impl<T: Copy> Sync for Constant<T> {}
+
+#[cfg(not(target_os = "cuda"))]
+impl<T: Copy> ConstantSymbol<T> {
+    pub const fn new(name: &'static CStr, type_name: &'static str) -> Self {
+        ConstantSymbol {
+            name,
+            type_name,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Symbol name to look up with `cuModuleGetGlobal`.
+    pub fn name(&self) -> &'static CStr {
+        self.name
+    }
+
+    /// Declared type, as written in the source.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Size of the variable in bytes.
+    pub fn size(&self) -> usize {
+        size_of::<T>()
+    }
+
+    /// Bytes of `value` to be copied into the symbol.
+    pub fn bytes_of<'a>(&self, value: &'a T) -> &'a [u8] {
+        unsafe { slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) }
+    }
+
+    /// Uploads `value` with a user-provided copy routine, e.g. `cuModuleGetGlobal` + `cuMemcpyHtoD`.
+    pub fn upload<E>(
+        &self,
+        value: &T,
+        copy: impl FnOnce(&'static CStr, &[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        copy(self.name, self.bytes_of(value))
+    }
+}
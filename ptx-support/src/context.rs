@@ -1,9 +1,21 @@
+#[cfg(target_os = "cuda")]
 use core::arch::nvptx::*;
 use core::cmp::PartialEq;
 use core::ops::Deref;
 
+#[cfg(not(target_os = "cuda"))]
+use crate::emu;
+#[cfg(not(target_os = "cuda"))]
+use crate::kernel::Dims;
+use crate::thread_group::ThreadGroup;
+
 pub struct Context;
 
+#[derive(Debug)]
+pub struct Grid {
+    dimensions: Vec3,
+}
+
 #[derive(Debug)]
 pub struct Block {
     dimensions: Vec3,
@@ -23,6 +35,20 @@ pub struct Vec3 {
 }
 
 impl Context {
+    #[cfg(target_os = "cuda")]
+    pub fn grid() -> Grid {
+        unsafe {
+            Grid {
+                dimensions: Vec3 {
+                    x: _grid_dim_x() as u64,
+                    y: _grid_dim_y() as u64,
+                    z: _grid_dim_z() as u64,
+                },
+            }
+        }
+    }
+
+    #[cfg(target_os = "cuda")]
     pub fn block() -> Block {
         unsafe {
             Block {
@@ -40,6 +66,7 @@ impl Context {
         }
     }
 
+    #[cfg(target_os = "cuda")]
     pub fn thread() -> Thread {
         unsafe {
             Thread {
@@ -51,6 +78,46 @@ impl Context {
             }
         }
     }
+
+    #[cfg(not(target_os = "cuda"))]
+    pub fn grid() -> Grid {
+        Grid {
+            dimensions: emu::current().grid_dims.into(),
+        }
+    }
+
+    #[cfg(not(target_os = "cuda"))]
+    pub fn block() -> Block {
+        let coords = emu::current();
+
+        Block {
+            dimensions: coords.block_dims.into(),
+            index: coords.block_index.into(),
+        }
+    }
+
+    #[cfg(not(target_os = "cuda"))]
+    pub fn thread() -> Thread {
+        Thread {
+            index: emu::current().thread_index.into(),
+        }
+    }
+
+    /// Index of the current thread among all threads of the grid.
+    pub fn global_thread_index() -> u64 {
+        Context::grid().thread_rank()
+    }
+
+    /// Amount of threads in the grid.
+    pub fn total_threads() -> u64 {
+        Context::grid().size()
+    }
+}
+
+impl Grid {
+    pub fn dims(&self) -> &Vec3 {
+        &self.dimensions
+    }
 }
 
 impl Block {
@@ -77,6 +144,17 @@ impl Thread {
     }
 }
 
+#[cfg(not(target_os = "cuda"))]
+impl From<Dims> for Vec3 {
+    fn from(dims: Dims) -> Self {
+        Vec3 {
+            x: u64::from(dims.x),
+            y: u64::from(dims.y),
+            z: u64::from(dims.z),
+        }
+    }
+}
+
 impl PartialEq<(u64, u64, u64)> for Vec3 {
     fn eq(&self, other: &(u64, u64, u64)) -> bool {
         self.x == other.0 && self.y == other.1 && self.z == other.2
@@ -101,14 +179,14 @@ impl PartialEq<(u64, u64)> for &Vec3 {
     }
 }
 
-impl PartialEq<(u64)> for Vec3 {
-    fn eq(&self, other: &(u64)) -> bool {
+impl PartialEq<u64> for Vec3 {
+    fn eq(&self, other: &u64) -> bool {
         self.x == *other && self.y == 0 && self.z == 0
     }
 }
 
-impl PartialEq<(u64)> for &Vec3 {
-    fn eq(&self, other: &(u64)) -> bool {
+impl PartialEq<u64> for &Vec3 {
+    fn eq(&self, other: &u64) -> bool {
         self.x == *other && self.y == 0 && self.z == 0
     }
 }
@@ -0,0 +1,177 @@
+//! CPU emulation of kernel launches, for testing kernel logic without a GPU.
+//!
+//! [`launch`] runs a closure once for every thread of the grid, one thread after another,
+//! and `Context` reports coordinates of the emulated thread. `cuda_printf!` prints to stdout.
+//!
+//! ```
+//! use ptx_support::emu;
+//! use ptx_support::prelude::*;
+//!
+//! let mut indices = vec![];
+//!
+//! emu::launch(2, (4, 2), || indices.push(Context::global_thread_index()));
+//!
+//! assert_eq!(indices, (0..16).collect::<Vec<_>>());
+//! ```
+//!
+//! `#[kernel]` entry points are only compiled for the device, so tests should call
+//! the functions implementing kernel logic. Block and grid synchronization is not supported,
+//! because threads are emulated sequentially.
+
+use std::cell::Cell;
+
+use crate::kernel::Dims;
+
+/// Launch coordinates of the emulated thread.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Coords {
+    pub grid_dims: Dims,
+    pub block_dims: Dims,
+    pub block_index: Dims,
+    pub thread_index: Dims,
+}
+
+std::thread_local! {
+    static CURRENT: Cell<Option<Coords>> = const { Cell::new(None) };
+}
+
+/// Restores coordinates of an outer launch, even when the kernel panics.
+struct Restore(Option<Coords>);
+
+/// Runs `kernel` for every thread of the grid, blocks and threads in the `x`, `y`, `z` order.
+pub fn launch<G, B, F>(grid: G, block: B, mut kernel: F)
+where
+    G: Into<Dims>,
+    B: Into<Dims>,
+    F: FnMut(),
+{
+    let grid_dims = grid.into();
+    let block_dims = block.into();
+    let _restore = Restore(CURRENT.with(Cell::get));
+
+    for block_index in indices(grid_dims) {
+        for thread_index in indices(block_dims) {
+            CURRENT.with(|current| {
+                current.set(Some(Coords {
+                    grid_dims,
+                    block_dims,
+                    block_index,
+                    thread_index,
+                }))
+            });
+
+            kernel();
+        }
+    }
+}
+
+pub(crate) fn current() -> Coords {
+    CURRENT
+        .with(Cell::get)
+        .expect("`Context` is only available inside of `emu::launch()`")
+}
+
+pub(crate) fn unsupported(feature: &str) -> ! {
+    panic!("{} is not supported by the emulation", feature);
+}
+
+fn indices(dims: Dims) -> impl Iterator<Item = Dims> {
+    (0..dims.z).flat_map(move |z| {
+        (0..dims.y).flat_map(move |y| (0..dims.x).map(move |x| Dims { x, y, z }))
+    })
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.set(self.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_launch() {
+        let mut coords = Vec::new();
+
+        launch((2, 1, 2), 3, || {
+            let block = Context::block();
+            let thread = Context::thread();
+
+            assert_eq!(Context::grid().dims(), &(2, 1, 2));
+            assert_eq!(block.dims(), &(3, 1, 1));
+            assert_eq!(Context::total_threads(), 12);
+
+            coords.push((
+                (block.index().x, block.index().z),
+                thread.index().x,
+                Context::global_thread_index(),
+            ));
+        });
+
+        assert_eq!(coords.len(), 12);
+        assert_eq!(coords[0], ((0, 0), 0, 0));
+        assert_eq!(coords[4], ((1, 0), 1, 4));
+        assert_eq!(coords[11], ((1, 1), 2, 11));
+    }
+
+    #[test]
+    fn test_outside_of_launch() {
+        launch(1, 1, || {
+            launch(1, 2, || {});
+            assert_eq!(Context::block().dims(), &(1, 1, 1));
+        });
+
+        let result = panic::catch_unwind(|| launch(1, 1, || panic!("kernel panic")));
+
+        assert!(result.is_err());
+        assert!(panic::catch_unwind(|| Context::thread().x).is_err());
+    }
+
+    #[test]
+    fn test_thread_coords() {
+        let mut coords = Vec::new();
+
+        launch((2, 1, 2), (1, 2, 2), || {
+            let block = Context::block();
+            let thread = Context::thread();
+
+            assert_eq!(thread.index(), (thread.x, thread.y, thread.z));
+
+            coords.push((
+                (block.index().x, block.index().y, block.index().z),
+                (thread.x, thread.y, thread.z),
+            ));
+        });
+
+        let mut expected = Vec::new();
+
+        for block in &[(0, 0, 0), (1, 0, 0), (0, 0, 1), (1, 0, 1)] {
+            for thread in &[(0, 0, 0), (0, 1, 0), (0, 0, 1), (0, 1, 1)] {
+                expected.push((*block, *thread));
+            }
+        }
+
+        assert_eq!(coords, expected);
+    }
+
+    // Conversions of the host format are checked by `test_host_format` of the macros crate.
+    #[cfg(feature = "macros")]
+    #[test]
+    fn test_printf() {
+        launch(1, 2, || {
+            cuda_printf!(
+                "thread %lu of %lu: %s %5.2f\n",
+                Context::thread().index().x,
+                Context::block().size(),
+                "value",
+                1.5,
+            );
+        });
+    }
+}
@@ -0,0 +1,239 @@
+//! Grid-stride loops.
+//!
+//! Every iterator reads the special registers once, when it's created,
+//! so the loop itself only increments and compares indices.
+
+use core::iter::FusedIterator;
+
+use crate::context::Context;
+
+/// Indices `k`, `k + T`, `k + 2 * T`, etc. below the limit, where `k` is the global index
+/// of the current thread and `T` is the total amount of threads.
+#[derive(Debug, Clone)]
+pub struct GridStrideRange {
+    next: usize,
+    end: usize,
+    step: usize,
+}
+
+/// Two-dimensional grid-stride loop over `(x, y)` coordinates.
+#[derive(Debug, Clone)]
+pub struct GridStrideRange2d {
+    x: Axis,
+    y: Axis,
+}
+
+/// Three-dimensional grid-stride loop over `(x, y, z)` coordinates.
+#[derive(Debug, Clone)]
+pub struct GridStrideRange3d {
+    x: Axis,
+    y: Axis,
+    z: Axis,
+}
+
+#[derive(Debug, Clone)]
+struct Axis {
+    start: usize,
+    next: usize,
+    end: usize,
+    step: usize,
+}
+
+impl Context {
+    /// Grid-stride loop over `0..n`, using the linear index of the thread in the grid.
+    pub fn grid_stride_range(n: usize) -> GridStrideRange {
+        GridStrideRange {
+            next: Context::global_thread_index() as usize,
+            end: n,
+            step: Context::total_threads() as usize,
+        }
+    }
+
+    /// Grid-stride loop over `width x height` coordinates, using `x` and `y` dimensions
+    /// of the grid. Rows are visited in order.
+    ///
+    /// Threads of a grid with more than one layer in `z` are folded into the `y` axis,
+    /// so every coordinate is still visited by exactly one thread.
+    pub fn grid_stride_range_2d(width: usize, height: usize) -> GridStrideRange2d {
+        let (x, y, z) = Axis::all(width, height, 1);
+        let start = z.start * y.step + y.start;
+
+        let y = Axis {
+            start,
+            next: start,
+            end: height,
+            step: y.step * z.step,
+        };
+
+        GridStrideRange2d { x, y }
+    }
+
+    /// Grid-stride loop over `width x height x depth` coordinates.
+    pub fn grid_stride_range_3d(width: usize, height: usize, depth: usize) -> GridStrideRange3d {
+        let (x, y, z) = Axis::all(width, height, depth);
+
+        GridStrideRange3d { x, y, z }
+    }
+}
+
+impl Axis {
+    fn new(start: u64, step: u64, end: usize) -> Self {
+        Axis {
+            start: start as usize,
+            next: start as usize,
+            end,
+            step: step as usize,
+        }
+    }
+
+    fn all(width: usize, height: usize, depth: usize) -> (Axis, Axis, Axis) {
+        let grid = Context::grid();
+        let block = Context::block();
+        let thread = Context::thread();
+
+        let x = Axis::new(
+            block.index().x * block.dims().x + thread.x,
+            grid.dims().x * block.dims().x,
+            width,
+        );
+
+        let y = Axis::new(
+            block.index().y * block.dims().y + thread.y,
+            grid.dims().y * block.dims().y,
+            height,
+        );
+
+        let z = Axis::new(
+            block.index().z * block.dims().z + thread.z,
+            grid.dims().z * block.dims().z,
+            depth,
+        );
+
+        (x, y, z)
+    }
+
+    fn is_done(&self) -> bool {
+        self.next >= self.end
+    }
+
+    fn advance(&mut self) {
+        self.next = self.next.saturating_add(self.step);
+    }
+
+    fn restart(&mut self) {
+        self.next = self.start;
+    }
+}
+
+impl Iterator for GridStrideRange {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        if self.next < self.end {
+            let index = self.next;
+            self.next = index.saturating_add(self.step);
+
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = match self.next < self.end {
+            true => (self.end - self.next - 1) / self.step + 1,
+            false => 0,
+        };
+
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for GridStrideRange {}
+impl FusedIterator for GridStrideRange {}
+
+impl Iterator for GridStrideRange2d {
+    type Item = (usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.x.is_done() {
+            self.x.restart();
+            self.y.advance();
+        }
+
+        if self.x.is_done() || self.y.is_done() {
+            return None;
+        }
+
+        let item = (self.x.next, self.y.next);
+        self.x.advance();
+
+        Some(item)
+    }
+}
+
+impl Iterator for GridStrideRange3d {
+    type Item = (usize, usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, usize, usize)> {
+        if self.x.is_done() {
+            self.x.restart();
+            self.y.advance();
+        }
+
+        if self.y.is_done() {
+            self.y.restart();
+            self.z.advance();
+        }
+
+        if self.x.is_done() || self.y.is_done() || self.z.is_done() {
+            return None;
+        }
+
+        let item = (self.x.next, self.y.next, self.z.next);
+        self.x.advance();
+
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::emu;
+
+    #[test]
+    fn test_grid_stride_range_2d() {
+        let mut visited = Vec::new();
+
+        emu::launch((1, 1, 2), (2, 1, 2), || {
+            visited.extend(Context::grid_stride_range_2d(3, 5));
+        });
+
+        visited.sort();
+
+        let expected: Vec<_> = (0..3).flat_map(|x| (0..5).map(move |y| (x, y))).collect();
+
+        assert_eq!(visited, expected);
+    }
+
+    #[test]
+    fn test_grid_stride_range_3d() {
+        let mut visited = Vec::new();
+
+        emu::launch((1, 2, 1), (2, 1, 2), || {
+            visited.extend(Context::grid_stride_range_3d(3, 3, 3));
+        });
+
+        visited.sort();
+
+        assert_eq!(visited.len(), 27);
+        visited.dedup();
+        assert_eq!(visited.len(), 27);
+    }
+}
@@ -0,0 +1,217 @@
+//! PTX special registers and instructions that are not (yet) available in `core::arch::nvptx`.
+
+use core::arch::{asm, global_asm};
+
+#[allow(improper_ctypes)]
+extern "C" {
+    #[link_name = "llvm.nvvm.read.ptx.sreg.laneid"]
+    fn nvvm_laneid() -> i32;
+
+    #[link_name = "llvm.nvvm.read.ptx.sreg.lanemask.lt"]
+    fn nvvm_lanemask_lt() -> i32;
+
+    #[link_name = "llvm.nvvm.activemask"]
+    fn nvvm_activemask() -> i32;
+
+    #[link_name = "llvm.nvvm.bar.warp.sync"]
+    fn nvvm_bar_warp_sync(mask: i32);
+
+    #[link_name = "llvm.nvvm.membar.gl"]
+    fn nvvm_membar_gl();
+
+    #[link_name = "llvm.nvvm.shfl.sync.bfly.i32"]
+    fn nvvm_shfl_sync_bfly_i32(mask: i32, value: i32, lane_mask: i32, clamp: i32) -> i32;
+
+    #[link_name = "llvm.nvvm.shfl.sync.up.i32"]
+    fn nvvm_shfl_sync_up_i32(mask: i32, value: i32, delta: i32, clamp: i32) -> i32;
+
+    #[link_name = "llvm.nvvm.shfl.sync.idx.i32"]
+    fn nvvm_shfl_sync_idx_i32(mask: i32, value: i32, lane: i32, clamp: i32) -> i32;
+}
+
+#[cfg(target_feature = "sm_80")]
+#[allow(improper_ctypes)]
+extern "C" {
+    #[link_name = "llvm.nvvm.redux.sync.add"]
+    fn nvvm_redux_sync_add(value: i32, mask: i32) -> i32;
+
+    #[link_name = "llvm.nvvm.redux.sync.min"]
+    fn nvvm_redux_sync_min(value: i32, mask: i32) -> i32;
+
+    #[link_name = "llvm.nvvm.redux.sync.max"]
+    fn nvvm_redux_sync_max(value: i32, mask: i32) -> i32;
+
+    #[link_name = "llvm.nvvm.redux.sync.umin"]
+    fn nvvm_redux_sync_umin(value: i32, mask: i32) -> i32;
+
+    #[link_name = "llvm.nvvm.redux.sync.umax"]
+    fn nvvm_redux_sync_umax(value: i32, mask: i32) -> i32;
+
+    #[link_name = "llvm.nvvm.redux.sync.and"]
+    fn nvvm_redux_sync_and(value: i32, mask: i32) -> i32;
+
+    #[link_name = "llvm.nvvm.redux.sync.or"]
+    fn nvvm_redux_sync_or(value: i32, mask: i32) -> i32;
+
+    #[link_name = "llvm.nvvm.redux.sync.xor"]
+    fn nvvm_redux_sync_xor(value: i32, mask: i32) -> i32;
+}
+
+// Lanes are never shuffled across the whole warp boundary.
+const SHFL_CLAMP: i32 = 0x1f;
+
+/// Index of the calling thread within its warp.
+#[inline(always)]
+pub fn lane_id() -> u32 {
+    unsafe { nvvm_laneid() as u32 }
+}
+
+/// Mask of warp lanes with an index lower than the calling thread's one.
+#[inline(always)]
+pub fn lanemask_lt() -> u32 {
+    unsafe { nvvm_lanemask_lt() as u32 }
+}
+
+/// Mask of currently active lanes in the warp.
+#[inline(always)]
+pub fn active_mask() -> u32 {
+    unsafe { nvvm_activemask() as u32 }
+}
+
+/// Synchronizes lanes listed in `mask`.
+#[inline(always)]
+pub fn warp_sync(mask: u32) {
+    unsafe { nvvm_bar_warp_sync(mask as i32) }
+}
+
+/// Makes memory writes of the calling thread visible to all threads of the device.
+#[inline(always)]
+pub fn thread_fence() {
+    unsafe { nvvm_membar_gl() }
+}
+
+/// Exchanges a 32-bit value with the lane `lane_id() ^ lane_mask`.
+#[inline(always)]
+pub fn shfl_xor(mask: u32, value: u32, lane_mask: u32) -> u32 {
+    unsafe {
+        nvvm_shfl_sync_bfly_i32(mask as i32, value as i32, lane_mask as i32, SHFL_CLAMP) as u32
+    }
+}
+
+/// Reads a 32-bit value from the lane `lane_id() - delta`, or keeps own value for lower lanes.
+#[inline(always)]
+pub fn shfl_up(mask: u32, value: u32, delta: u32) -> u32 {
+    unsafe { nvvm_shfl_sync_up_i32(mask as i32, value as i32, delta as i32, 0) as u32 }
+}
+
+/// Reads a 32-bit value from the lane `lane`.
+#[inline(always)]
+pub fn shfl_idx(mask: u32, value: u32, lane: u32) -> u32 {
+    unsafe { nvvm_shfl_sync_idx_i32(mask as i32, value as i32, lane as i32, SHFL_CLAMP) as u32 }
+}
+
+global_asm!(".extern .shared .align 16 .b8 __ptx_support_dynamic_shared[];");
+
+/// Alignment of the dynamic shared memory segment.
+pub const DYNAMIC_SHARED_ALIGNMENT: usize = 16;
+
+/// Generic address of the dynamic shared memory segment.
+#[inline(always)]
+pub fn dynamic_shared_base() -> *mut u8 {
+    let address: u64;
+
+    unsafe {
+        asm!("cvta.shared.u64 {}, __ptx_support_dynamic_shared;", out(reg64) address);
+    }
+
+    address as *mut u8
+}
+
+/// Size in bytes of the dynamic shared memory segment, as requested by the launch.
+#[inline(always)]
+pub fn dynamic_shared_size() -> usize {
+    let size: u32;
+
+    unsafe {
+        asm!("mov.u32 {}, %dynamic_smem_size;", out(reg32) size);
+    }
+
+    size as usize
+}
+
+/// Warp-wide reductions of 32-bit integers performed with `redux.sync`.
+#[cfg(target_feature = "sm_80")]
+pub mod redux {
+    use super::*;
+
+    macro_rules! redux {
+        ($($name:ident => $intrinsic:ident,)+) => {$(
+            #[inline(always)]
+            pub fn $name(mask: u32, value: u32) -> u32 {
+                unsafe { $intrinsic(value as i32, mask as i32) as u32 }
+            }
+        )+};
+    }
+
+    redux! {
+        add => nvvm_redux_sync_add,
+        min => nvvm_redux_sync_min,
+        max => nvvm_redux_sync_max,
+        umin => nvvm_redux_sync_umin,
+        umax => nvvm_redux_sync_umax,
+        and => nvvm_redux_sync_and,
+        or => nvvm_redux_sync_or,
+        xor => nvvm_redux_sync_xor,
+    }
+}
+
+/// Asynchronous copies from global to shared memory.
+#[cfg(target_feature = "sm_80")]
+pub mod cp_async {
+    use super::*;
+
+    /// Copies `size` bytes (4, 8 or 16), caching the data at all levels.
+    #[inline(always)]
+    pub unsafe fn copy_ca(shared: u64, global: u64, size: usize) {
+        match size {
+            4 => {
+                asm!("cp.async.ca.shared.global [{}], [{}], 4;", in(reg64) shared, in(reg64) global)
+            }
+            8 => {
+                asm!("cp.async.ca.shared.global [{}], [{}], 8;", in(reg64) shared, in(reg64) global)
+            }
+            16 => {
+                asm!("cp.async.ca.shared.global [{}], [{}], 16;", in(reg64) shared, in(reg64) global)
+            }
+            _ => panic!("Asynchronous copies are only possible for 4, 8 or 16 bytes"),
+        }
+    }
+
+    /// Copies 16 bytes, caching the data in L2 only.
+    #[inline(always)]
+    pub unsafe fn copy_cg(shared: u64, global: u64) {
+        asm!("cp.async.cg.shared.global [{}], [{}], 16;", in(reg64) shared, in(reg64) global);
+    }
+
+    #[inline(always)]
+    pub fn commit_group() {
+        unsafe { asm!("cp.async.commit_group;") }
+    }
+
+    /// Waits until at most `pending` of the most recent groups are still in flight.
+    #[inline(always)]
+    pub fn wait_group(pending: usize) {
+        unsafe {
+            match pending {
+                0 => asm!("cp.async.wait_group 0;"),
+                1 => asm!("cp.async.wait_group 1;"),
+                2 => asm!("cp.async.wait_group 2;"),
+                3 => asm!("cp.async.wait_group 3;"),
+                4 => asm!("cp.async.wait_group 4;"),
+                5 => asm!("cp.async.wait_group 5;"),
+                6 => asm!("cp.async.wait_group 6;"),
+                _ => asm!("cp.async.wait_group 7;"),
+            }
+        }
+    }
+}
@@ -0,0 +1,438 @@
+//! Kernels declared with the `#[kernel]` attribute.
+//!
+//! ```ignore
+//! #[kernel]
+//! pub fn add(a: DeviceSlice<f32>, b: DeviceSlice<f32>, mut output: DeviceSliceMut<f32>) {
+//!     // ...
+//! }
+//! ```
+//!
+//! On the device the function becomes a `#[no_mangle] unsafe extern "ptx-kernel"` entry point.
+//! The body is kept in a safe function called by the entry point, so calls of unsafe functions
+//! still need an `unsafe` block:
+//!
+//! ```compile_fail,E0133
+//! // Shape of the expanded entry point.
+//! unsafe extern "C" fn example_kernel(index: usize) {
+//!     #[inline(always)]
+//!     fn __kernel_body(index: usize) {
+//!         read_unchecked(index);
+//!     }
+//!
+//!     __kernel_body(index)
+//! }
+//!
+//! unsafe fn read_unchecked(_index: usize) {}
+//! ```
+//!
+//! On the host the same declaration turns into a unit struct named `AddKernel`,
+//! which implements [`Kernel`] and describes the entry point signature.
+//! The struct also gets typed launch functions, which pass arguments to a [`Driver`]:
+//!
+//! ```ignore
+//! AddKernel::launch(&driver, grid, block, a, b, output)?;
+//! ```
+//!
+//! Launch bounds are declared as `#[kernel(max_threads = 256, min_blocks_per_sm = 2)]`
+//! or `#[kernel(required_threads = (32, 8, 1))]`. They are available as [`Kernel::BOUNDS`],
+//! and `ptx_support_build::apply_launch_bounds()` adds them to the PTX assembly.
+//!
+//! Every parameter has to implement [`KernelParam`]. Structs can derive it:
+//!
+//! ```ignore
+//! #[repr(C)]
+//! #[derive(Clone, Copy, KernelParam)]
+//! pub struct Params {
+//!     scale: f32,
+//!     offset: [f32; 2],
+//! }
+//! ```
+
+use core::any::{type_name, TypeId};
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of, transmute};
+
+/// Parameter of a kernel entry point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamSignature {
+    pub name: &'static str,
+    pub ty: &'static str,
+    pub layout: ParamLayout,
+}
+
+/// Host-side description of a kernel entry point.
+pub trait Kernel {
+    /// Name of the entry point in the PTX assembly.
+    const NAME: &'static str;
+
+    /// Parameters of the entry point, in order.
+    const PARAMS: &'static [ParamSignature];
+
+    /// Launch bounds declared with `#[kernel(...)]`.
+    const BOUNDS: LaunchBounds = LaunchBounds::NONE;
+}
+
+/// Types which can be passed by value from the host to a kernel.
+///
+/// # Safety
+/// The type has to be FFI-safe, must not own or borrow anything, and has to have
+/// the same layout on the host and on the device.
+pub unsafe trait KernelParam {
+    const LAYOUT: ParamLayout;
+}
+
+/// Memory layout of a kernel parameter type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamLayout {
+    pub name: &'static str,
+
+    /// Module path of a struct deriving `KernelParam`, e.g. `kernels::blur`, empty otherwise.
+    pub path: &'static str,
+    pub size: usize,
+    pub align: usize,
+    pub fields: &'static [FieldLayout],
+}
+
+/// Memory layout of a field of a struct kernel parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldLayout {
+    pub name: &'static str,
+    pub ty: &'static str,
+    pub offset: usize,
+    pub size: usize,
+    pub align: usize,
+}
+
+impl ParamLayout {
+    /// Layout of a type without inspectable fields.
+    pub const fn of<T>(name: &'static str) -> Self {
+        ParamLayout {
+            name,
+            path: "",
+            size: size_of::<T>(),
+            align: align_of::<T>(),
+            fields: &[],
+        }
+    }
+}
+
+/// Grid or block dimensions of a launch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dims {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+impl From<u32> for Dims {
+    fn from(x: u32) -> Self {
+        Dims { x, y: 1, z: 1 }
+    }
+}
+
+impl From<(u32, u32)> for Dims {
+    fn from((x, y): (u32, u32)) -> Self {
+        Dims { x, y, z: 1 }
+    }
+}
+
+impl From<(u32, u32, u32)> for Dims {
+    fn from((x, y, z): (u32, u32, u32)) -> Self {
+        Dims { x, y, z }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaunchConfig {
+    pub grid: Dims,
+    pub block: Dims,
+
+    /// Amount of dynamic shared memory per block in bytes.
+    pub shared_memory: u32,
+}
+
+impl LaunchConfig {
+    pub fn new(grid: impl Into<Dims>, block: impl Into<Dims>) -> Self {
+        LaunchConfig {
+            grid: grid.into(),
+            block: block.into(),
+            shared_memory: 0,
+        }
+    }
+
+    pub fn with_shared_memory(self, shared_memory: u32) -> Self {
+        LaunchConfig {
+            shared_memory,
+            ..self
+        }
+    }
+}
+
+/// Borrowed kernel argument, in the form expected by `cuLaunchKernel`.
+#[derive(Debug, Clone, Copy)]
+pub struct LaunchArg<'a> {
+    value: *const c_void,
+    layout: ParamLayout,
+    type_id: TypeId,
+    type_name: &'static str,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> LaunchArg<'a> {
+    pub fn new<T: KernelParam>(value: &'a T) -> Self {
+        LaunchArg {
+            value: value as *const T as *const c_void,
+            layout: T::LAYOUT,
+            type_id: erased_type_id::<T>(),
+            type_name: type_name::<T>(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Pointer to the argument value.
+    pub fn as_ptr(&self) -> *const c_void {
+        self.value
+    }
+
+    pub fn layout(&self) -> &ParamLayout {
+        &self.layout
+    }
+
+    /// Full name of the argument type, for diagnostics only.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Copy of the argument value, if it has the type `T`.
+    ///
+    /// Types are compared by their `TypeId`. Arguments such as `DeviceSlice<'a, T>` borrow
+    /// device memory, so lifetimes are erased: the copy has the `'static` lifetime,
+    /// which is harmless on the host, where parameters can't be dereferenced.
+    pub fn value<T: KernelParam + Copy + 'static>(&self) -> Option<T> {
+        match TypeId::of::<T>() == self.type_id && T::LAYOUT == self.layout {
+            true => Some(unsafe { *(self.value as *const T) }),
+            false => None,
+        }
+    }
+}
+
+/// `TypeId` of a type which can borrow, with all lifetimes replaced by `'static`.
+fn erased_type_id<T: ?Sized>() -> TypeId {
+    trait NonStaticAny {
+        fn type_id(&self) -> TypeId
+        where
+            Self: 'static;
+    }
+
+    impl<T: ?Sized> NonStaticAny for PhantomData<T> {
+        fn type_id(&self) -> TypeId
+        where
+            Self: 'static,
+        {
+            TypeId::of::<T>()
+        }
+    }
+
+    let phantom = PhantomData::<T>;
+    let erased = &phantom as &dyn NonStaticAny;
+
+    // Lifetimes don't take part in the dispatch, so the same implementation is called.
+    unsafe { transmute::<&dyn NonStaticAny, &(dyn NonStaticAny + 'static)>(erased) }.type_id()
+}
+
+/// Launch bounds of a kernel, which become `.maxntid`, `.minnctapersm` and `.reqntid`
+/// directives of the entry point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaunchBounds {
+    pub max_threads: Option<Dims>,
+    pub min_blocks_per_sm: Option<u32>,
+    pub required_threads: Option<Dims>,
+}
+
+impl LaunchBounds {
+    pub const NONE: Self = LaunchBounds {
+        max_threads: None,
+        min_blocks_per_sm: None,
+        required_threads: None,
+    };
+
+    /// Checks whether the kernel can be launched with blocks of `block` threads.
+    pub fn accepts(&self, block: Dims) -> bool {
+        let total = |dims: Dims| u64::from(dims.x) * u64::from(dims.y) * u64::from(dims.z);
+
+        match (self.required_threads, self.max_threads) {
+            (Some(required), _) => block == required,
+            (None, Some(max)) => total(block) <= total(max),
+            (None, None) => true,
+        }
+    }
+}
+
+/// Launches kernels for the generated launch functions.
+///
+/// Implemented on top of an actual CUDA driver binding, or by a mock in tests.
+pub trait Driver {
+    type Error;
+
+    /// Launches the entry point `name`. Arguments are in the order of kernel parameters.
+    fn launch_kernel(
+        &self,
+        name: &str,
+        config: &LaunchConfig,
+        args: &[LaunchArg],
+    ) -> Result<(), Self::Error>;
+}
+
+macro_rules! impl_primitive_param {
+    ($($ty:ty,)+) => {$(
+        unsafe impl KernelParam for $ty {
+            const LAYOUT: ParamLayout = ParamLayout::of::<$ty>(stringify!($ty));
+        }
+    )+};
+}
+
+impl_primitive_param! {
+    u8, u16, u32, u64, usize,
+    i8, i16, i32, i64, isize,
+    f32, f64, bool,
+}
+
+unsafe impl<T> KernelParam for *const T {
+    const LAYOUT: ParamLayout = ParamLayout::of::<*const T>("pointer");
+}
+
+unsafe impl<T> KernelParam for *mut T {
+    const LAYOUT: ParamLayout = ParamLayout::of::<*mut T>("pointer");
+}
+
+unsafe impl<T: KernelParam, const N: usize> KernelParam for [T; N] {
+    const LAYOUT: ParamLayout = ParamLayout::of::<[T; N]>("array");
+}
+
+/// Used by `#[kernel]` and `#[derive(KernelParam)]` to point at unsuitable types.
+#[doc(hidden)]
+pub fn assert_kernel_param<T: KernelParam>() {}
+
+#[doc(hidden)]
+pub fn assert_copy<T: Copy>() {}
+
+#[cfg(all(test, feature = "macros"))]
+mod tests {
+    use core::cell::Cell;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[kernel]
+    pub fn scale(_factor: f32, _count: u32) {}
+
+    #[derive(Default)]
+    struct MockDriver {
+        launched: Cell<Option<(LaunchConfig, f32, u32)>>,
+    }
+
+    impl Driver for MockDriver {
+        type Error = &'static str;
+
+        fn launch_kernel(
+            &self,
+            name: &str,
+            config: &LaunchConfig,
+            args: &[LaunchArg],
+        ) -> Result<(), Self::Error> {
+            assert_eq!(name, "scale");
+
+            match args {
+                [factor, count] => {
+                    let factor = factor.value().ok_or("factor has a wrong type")?;
+                    let count = count.value().ok_or("count has a wrong type")?;
+
+                    self.launched.set(Some((*config, factor, count)));
+                    Ok(())
+                }
+
+                _ => Err("wrong amount of arguments"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_launch() {
+        let driver = MockDriver::default();
+
+        ScaleKernel::launch(&driver, 4, (8, 8), 0.5, 10).unwrap();
+
+        assert_eq!(
+            driver.launched.get(),
+            Some((LaunchConfig::new((4, 1, 1), (8, 8, 1)), 0.5, 10))
+        );
+    }
+
+    #[test]
+    fn test_launch_with() {
+        let driver = MockDriver::default();
+        let config = LaunchConfig::new(1, 32).with_shared_memory(1024);
+
+        ScaleKernel::launch_with(&driver, config, 2.0, 3).unwrap();
+
+        assert_eq!(driver.launched.get(), Some((config, 2.0, 3)));
+    }
+
+    #[kernel(required_threads = (8, 8))]
+    pub fn tile(_factor: f32, _count: u32) {}
+
+    #[test]
+    fn test_launch_bounds() {
+        assert_eq!(ScaleKernel::BOUNDS, LaunchBounds::NONE);
+        assert_eq!(
+            TileKernel::BOUNDS.required_threads,
+            Some(Dims::from((8, 8)))
+        );
+
+        assert!(TileKernel::BOUNDS.accepts((8, 8).into()));
+        assert!(!TileKernel::BOUNDS.accepts(64.into()));
+
+        let bounds = LaunchBounds {
+            max_threads: Some(256.into()),
+            ..LaunchBounds::NONE
+        };
+
+        assert!(bounds.accepts((16, 16).into()));
+        assert!(!bounds.accepts((16, 32).into()));
+    }
+
+    #[test]
+    fn test_arg_value() {
+        let arg = LaunchArg::new(&42u32);
+
+        assert_eq!(arg.value::<u32>(), Some(42));
+        assert_eq!(arg.value::<f32>(), None);
+
+        let items = [1u8, 2, 3];
+        let arg = LaunchArg::new(&items);
+
+        assert_eq!(arg.value::<[u8; 3]>(), Some(items));
+        assert_eq!(arg.value::<[i8; 3]>(), None);
+
+        let pointer = items.as_ptr();
+        let arg = LaunchArg::new(&pointer);
+
+        assert_eq!(arg.value::<*const u8>(), Some(pointer));
+        assert_eq!(arg.value::<*const u16>(), None);
+        assert_eq!(arg.value::<*mut u8>(), None);
+        assert_eq!(arg.type_name(), "*const u8");
+
+        let data = [1.5f32, 2.5];
+        let slice = unsafe { crate::slice::DeviceSlice::from_raw_parts(data.as_ptr(), 2) };
+        let arg = LaunchArg::new(&slice);
+
+        let value = arg
+            .value::<crate::slice::DeviceSlice<'static, f32>>()
+            .unwrap();
+        assert_eq!((value.as_ptr(), value.len()), (data.as_ptr(), 2));
+        assert!(arg
+            .value::<crate::slice::DeviceSlice<'static, u32>>()
+            .is_none());
+    }
+}
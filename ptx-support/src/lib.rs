@@ -2,19 +2,87 @@
 #![no_std]
 #![cfg_attr(
     target_os = "cuda",
-    feature(proc_macro_hygiene, core_intrinsics, stdsimd)
+    feature(
+        proc_macro_hygiene,
+        core_intrinsics,
+        stdsimd,
+        link_llvm_intrinsics,
+        asm_experimental_arch
+    )
+)]
+#![cfg_attr(
+    all(target_os = "cuda", feature = "alloc"),
+    feature(alloc_error_handler)
 )]
 
-#[cfg(target_os = "cuda")]
+// The emulation backend needs thread-local storage and `cuda_printf!` prints to stdout.
+#[cfg(not(target_os = "cuda"))]
+extern crate std;
+
+// Generated code refers to `::ptx_support`, which also has to work in unit tests.
+#[cfg(test)]
+extern crate self as ptx_support;
+
+#[cfg(all(target_os = "cuda", feature = "alloc"))]
+pub mod allocator;
+
+// Index arithmetic of block collectives is tested on the host.
+#[cfg(any(target_os = "cuda", test))]
+pub mod block;
+
+pub mod constant;
+
 mod context;
 
+#[cfg(not(target_os = "cuda"))]
+pub mod emu;
+
+pub mod grid_stride;
+
+#[cfg(target_os = "cuda")]
+mod intrinsics;
+
+pub mod kernel;
+
+pub mod manifest;
+
 #[cfg(target_os = "cuda")]
 mod panic_handler;
 
+#[cfg(all(target_os = "cuda", target_feature = "sm_80"))]
+pub mod pipeline;
+
+pub mod pitched;
+
+pub mod ptr;
+
 #[cfg(target_os = "cuda")]
+pub mod shared;
+
+pub mod slice;
+
+pub mod thread_group;
+
+// Warp collectives are tested on the host with emulated shuffles.
+#[cfg(any(target_os = "cuda", test))]
+pub mod warp;
+
 pub mod prelude {
     #[cfg(feature = "macros")]
     pub use ptx_support_macros::*;
 
-    pub use crate::context::Context;
+    #[cfg(all(target_os = "cuda", feature = "alloc"))]
+    pub use crate::allocator::CudaAllocator;
+
+    #[cfg(target_os = "cuda")]
+    pub use crate::shared::SharedArray;
+
+    pub use crate::{
+        context::{Block, Context, Grid},
+        kernel::{Kernel, KernelParam},
+        pitched::{Pitched2d, Pitched2dMut, Pitched3d, Pitched3dMut},
+        ptr::{ConstPtr, GlobalPtr, LocalPtr, SharedPtr},
+        slice::{DeviceSlice, DeviceSliceMut},
+        thread_group::ThreadGroup,
+    };
 }
@@ -0,0 +1,118 @@
+//! Compile-time records describing device symbols to the host.
+//!
+//! Macros such as `#[constant]` embed a record into the module as a global byte string named
+//! `__ptx_support_manifest_<kind>_<name>`. Every record is a single line of tab-separated fields,
+//! so host tools can find them next to the PTX assembly.
+
+/// Prefix of manifest record symbols.
+pub const SYMBOL_PREFIX: &str = "__ptx_support_manifest_";
+
+/// Single field of a manifest record.
+#[derive(Debug, Clone, Copy)]
+pub enum Field {
+    Str(&'static str),
+    Num(usize),
+}
+
+/// Length of the encoded record in bytes.
+pub const fn encoded_len(fields: &[Field]) -> usize {
+    let mut len = 0;
+    let mut index = 0;
+
+    while index < fields.len() {
+        len += match fields[index] {
+            Field::Str(value) => value.len(),
+            Field::Num(value) => digits_count(value),
+        };
+
+        index += 1;
+    }
+
+    // Separators and the trailing newline.
+    len + fields.len()
+}
+
+/// Encodes fields as a tab-separated line.
+pub const fn encode<const N: usize>(fields: &[Field]) -> [u8; N] {
+    let mut output = [0; N];
+    let mut position = 0;
+    let mut index = 0;
+
+    while index < fields.len() {
+        if index > 0 {
+            output[position] = b'\t';
+            position += 1;
+        }
+
+        match fields[index] {
+            Field::Str(value) => {
+                let bytes = value.as_bytes();
+                let mut byte = 0;
+
+                while byte < bytes.len() {
+                    output[position] = bytes[byte];
+                    position += 1;
+                    byte += 1;
+                }
+            }
+
+            Field::Num(value) => {
+                let count = digits_count(value);
+                let mut rest = value;
+                let mut digit = count;
+
+                while digit > 0 {
+                    output[position + digit - 1] = b'0' + (rest % 10) as u8;
+                    rest /= 10;
+                    digit -= 1;
+                }
+
+                position += count;
+            }
+        }
+
+        index += 1;
+    }
+
+    output[position] = b'\n';
+    output
+}
+
+const fn digits_count(mut value: usize) -> usize {
+    let mut count = 1;
+
+    while value >= 10 {
+        value /= 10;
+        count += 1;
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        const FIELDS: &[Field] = &[
+            Field::Str("constant"),
+            Field::Str("LUT"),
+            Field::Str("[f32; 256]"),
+            Field::Num(1024),
+            Field::Num(4),
+        ];
+
+        const ENCODED: [u8; encoded_len(FIELDS)] = encode(FIELDS);
+
+        assert_eq!(&ENCODED[..], &b"constant\tLUT\t[f32; 256]\t1024\t4\n"[..]);
+    }
+
+    #[test]
+    fn test_encode_zero() {
+        const FIELDS: &[Field] = &[Field::Num(0), Field::Num(10)];
+        const ENCODED: [u8; encoded_len(FIELDS)] = encode(FIELDS);
+
+        assert_eq!(&ENCODED[..], &b"0\t10\n"[..]);
+    }
+}
@@ -0,0 +1,209 @@
+//! Multi-stage pipeline of asynchronous copies from global to shared memory (sm_80+).
+//!
+//! Every stage owns a shared buffer, carved from the dynamic shared memory segment. A stage is
+//! filled with `cp.async` copies, committed as a single group and can only be read after
+//! the group has completed:
+//!
+//! ```ignore
+//! let mut regions = unsafe { Context::dynamic_shared_regions() };
+//! let mut pipeline = Pipeline::<f32, 2>::new(&mut regions, TILE_SIZE);
+//!
+//! // Safety: every tile has `TILE_SIZE` elements in global memory,
+//! // which are not written while the kernel runs.
+//! pipeline.produce(|stage| unsafe { stage.copy_owned(first_tile, CopyCache::All) });
+//!
+//! for tile in 1..=tiles_count {
+//!     if tile < tiles_count {
+//!         pipeline.produce(|stage| unsafe { stage.copy_owned(next_tile(tile), CopyCache::All) });
+//!     }
+//!
+//!     pipeline.consume(|data| process(data));
+//! }
+//! ```
+//!
+//! Every thread of the block has to go through the same sequence of `produce` and `consume`.
+
+use core::mem::size_of;
+
+use crate::context::{Block, Context};
+use crate::intrinsics::cp_async;
+use crate::ptr::{AddressSpace, GlobalPtr, Shared};
+use crate::shared::{DynamicSharedRegions, SharedReader, SharedSlice};
+use crate::thread_group::ThreadGroup;
+
+/// Cache operator of an asynchronous copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyCache {
+    /// Cache at all levels (`.ca`).
+    All,
+    /// Cache in L2 only (`.cg`), only possible for 16 byte elements.
+    Global,
+}
+
+/// Ring of `STAGES` shared buffers filled asynchronously.
+pub struct Pipeline<'a, T, const STAGES: usize> {
+    buffers: [&'a SharedSlice<T>; STAGES],
+    produced: usize,
+    consumed: usize,
+}
+
+/// Stage being filled by the current thread.
+///
+/// The thread with block rank `r` owns elements `r`, `r + block size`, `r + 2 * block size`, etc.
+pub struct Stage<'a, T> {
+    buffer: &'a SharedSlice<T>,
+    block: Block,
+}
+
+impl<'a, T: Copy, const STAGES: usize> Pipeline<'a, T, STAGES> {
+    const ELEMENT_CHECK: () = assert!(
+        size_of::<T>() == 4 || size_of::<T>() == 8 || size_of::<T>() == 16,
+        "Asynchronous copies are only possible for 4, 8 or 16 byte elements"
+    );
+
+    /// Carves `STAGES` buffers of `len` elements, owned by the pipeline.
+    ///
+    /// Every buffer is aligned to the element size, as required by asynchronous copies.
+    ///
+    /// Panics when the rest of the segment is too small.
+    pub fn new(regions: &mut DynamicSharedRegions, len: usize) -> Self
+    where
+        T: 'static,
+    {
+        let mut buffers = [None; STAGES];
+
+        for buffer in buffers.iter_mut() {
+            regions.align_to(size_of::<T>());
+            *buffer = Some(regions.slice(len));
+        }
+
+        unsafe { Self::from_buffers(buffers.map(Option::unwrap)) }
+    }
+
+    /// Creates a pipeline from buffers declared elsewhere, e.g. with `#[shared]`.
+    ///
+    /// # Safety
+    /// The buffers have to be distinct and must not be accessed by anything else
+    /// while the pipeline is in use.
+    pub unsafe fn from_buffers(buffers: [&'a SharedSlice<T>; STAGES]) -> Self {
+        let () = Self::ELEMENT_CHECK;
+
+        Pipeline {
+            buffers,
+            produced: 0,
+            consumed: 0,
+        }
+    }
+
+    /// Amount of stages which were committed but not consumed yet.
+    pub fn in_flight(&self) -> usize {
+        self.produced - self.consumed
+    }
+
+    /// Issues copies into the next free buffer and commits them as a single stage.
+    ///
+    /// Panics when every buffer is still in flight.
+    pub fn produce<F: FnOnce(&mut Stage<'a, T>)>(&mut self, fill: F) {
+        assert!(
+            self.in_flight() < STAGES,
+            "Every pipeline stage is in flight, it has to be consumed first"
+        );
+
+        let mut stage = Stage {
+            buffer: self.buffers[self.produced % STAGES],
+            block: Context::block(),
+        };
+
+        fill(&mut stage);
+        cp_async::commit_group();
+
+        self.produced += 1;
+    }
+
+    /// Waits for the oldest stage and gives read access to its buffer.
+    ///
+    /// The buffer is released for new copies after `read` returns.
+    /// Only elements copied during `produce` can be read from the buffer.
+    /// Panics when no stage is in flight.
+    pub fn consume<R, F: FnOnce(&SharedReader<'a, T>) -> R>(&mut self, read: F) -> R {
+        assert!(self.in_flight() > 0, "No pipeline stage is in flight");
+
+        let block = Context::block();
+
+        cp_async::wait_group(self.in_flight() - 1);
+        block.sync();
+
+        let reader = unsafe { self.buffers[self.consumed % STAGES].reader() };
+        let result = read(&reader);
+
+        block.sync();
+        self.consumed += 1;
+
+        result
+    }
+}
+
+impl<'a, T: Copy> Stage<'a, T> {
+    /// Starts an asynchronous copy of an element owned by the current thread.
+    ///
+    /// Panics when the element belongs to another thread.
+    ///
+    /// # Safety
+    /// `source` has to point to a valid element, which is not written until the stage is consumed.
+    pub unsafe fn copy(&mut self, index: usize, source: GlobalPtr<T>, cache: CopyCache) {
+        assert!(
+            index as u64 % self.block.size() == self.block.thread_rank(),
+            "Shared slice element is owned by another thread"
+        );
+
+        let destination = Shared::to_space(self.buffer.item(index) as *const u8);
+
+        debug_assert!(
+            destination % size_of::<T>() as u64 == 0
+                && source.space_address() % size_of::<T>() as u64 == 0,
+            "Asynchronous copies have to be aligned to the element size"
+        );
+
+        match cache {
+            CopyCache::All => {
+                cp_async::copy_ca(destination, source.space_address(), size_of::<T>())
+            }
+
+            CopyCache::Global => {
+                assert!(
+                    size_of::<T>() == 16,
+                    "L2-only asynchronous copies are only possible for 16 byte elements"
+                );
+
+                cp_async::copy_cg(destination, source.space_address())
+            }
+        }
+    }
+
+    /// Copies every owned element from consecutive global memory.
+    ///
+    /// # Safety
+    /// `source` has to point to as many valid elements as the buffer has,
+    /// which are not written until the stage is consumed.
+    pub unsafe fn copy_owned(&mut self, source: GlobalPtr<T>, cache: CopyCache) {
+        for index in self.owned_indices() {
+            self.copy(index, source.add(index), cache);
+        }
+    }
+
+    /// Indices of buffer elements owned by the current thread.
+    pub fn owned_indices(&self) -> impl Iterator<Item = usize> {
+        let rank = self.block.thread_rank() as usize;
+        let stride = self.block.size() as usize;
+
+        (rank..self.buffer.len()).step_by(stride)
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+}
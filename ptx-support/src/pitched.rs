@@ -0,0 +1,476 @@
+//! Pitched 2D and 3D arrays passed as kernel parameters.
+//!
+//! Rows of a pitched allocation (e.g. made by `cuMemAllocPitch`) are `pitch` bytes apart,
+//! which can be more than `width` elements. Layouts match `(pointer, width, height, pitch)`
+//! and `(pointer, width, height, depth, pitch)` tuples.
+//!
+//! Elements of mutable arrays are partitioned between threads of the grid
+//! in the same way as `Context::grid_stride_range_2d()` and `Context::grid_stride_range_3d()`
+//! visit coordinates, so every element is handed out safely to exactly one thread
+//! for any launch shape.
+//!
+//! Pitches are checked when arrays are created, and again by every accessor in debug builds,
+//! because arrays are usually passed from the host as kernel parameters.
+
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of};
+#[cfg(target_os = "cuda")]
+use core::ops::Index;
+#[cfg(target_os = "cuda")]
+use core::slice;
+
+#[cfg(target_os = "cuda")]
+use crate::context::Context;
+use crate::kernel::{KernelParam, ParamLayout};
+
+/// Read-only pitched 2D array.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Pitched2d<'a, T> {
+    data: *const T,
+    width: usize,
+    height: usize,
+    pitch: usize,
+    _marker: PhantomData<&'a [T]>,
+}
+
+/// Mutable pitched 2D array, partitioned between threads of the grid.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Pitched2dMut<'a, T> {
+    data: *mut T,
+    width: usize,
+    height: usize,
+    pitch: usize,
+    _marker: PhantomData<&'a mut [T]>,
+}
+
+/// Read-only pitched 3D array: `depth` planes of `height` rows each.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Pitched3d<'a, T> {
+    data: *const T,
+    width: usize,
+    height: usize,
+    depth: usize,
+    pitch: usize,
+    _marker: PhantomData<&'a [T]>,
+}
+
+/// Mutable pitched 3D array, partitioned between threads of the grid.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Pitched3dMut<'a, T> {
+    data: *mut T,
+    width: usize,
+    height: usize,
+    depth: usize,
+    pitch: usize,
+    _marker: PhantomData<&'a mut [T]>,
+}
+
+fn check_pitch<T>(width: usize, pitch: usize) {
+    assert!(
+        pitch >= width * size_of::<T>() && pitch % align_of::<T>() == 0,
+        "Pitch is smaller than a row or breaks the alignment of elements"
+    );
+}
+
+#[cfg(target_os = "cuda")]
+fn debug_check_pitch<T>(width: usize, pitch: usize) {
+    if cfg!(debug_assertions) {
+        check_pitch::<T>(width, pitch);
+    }
+}
+
+/// Pointer to the first element of the row `y`, without bounds checks.
+unsafe fn row_ptr<T>(data: *const T, pitch: usize, y: usize) -> *const T {
+    (data as *const u8).add(y * pitch) as *const T
+}
+
+impl<'a, T> Pitched2d<'a, T> {
+    /// # Safety
+    /// `data` has to point to `height` rows of `width` initialized elements, `pitch` bytes apart,
+    /// which are not written by anyone for the lifetime `'a`.
+    pub unsafe fn from_raw_parts(
+        data: *const T,
+        width: usize,
+        height: usize,
+        pitch: usize,
+    ) -> Self {
+        check_pitch::<T>(width, pitch);
+
+        Pitched2d {
+            data,
+            width,
+            height,
+            pitch,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Distance between rows in bytes.
+    pub fn pitch(&self) -> usize {
+        self.pitch
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl<'a, T> Pitched2d<'a, T> {
+    pub fn get(&self, x: usize, y: usize) -> Option<&'a T> {
+        debug_check_pitch::<T>(self.width, self.pitch);
+
+        match x < self.width && y < self.height {
+            true => Some(unsafe { &*row_ptr(self.data, self.pitch, y).add(x) }),
+            false => None,
+        }
+    }
+
+    /// Panics when the row is out of bounds.
+    pub fn row(&self, y: usize) -> &'a [T] {
+        assert!(y < self.height, "Pitched array row is out of bounds");
+        debug_check_pitch::<T>(self.width, self.pitch);
+
+        unsafe { slice::from_raw_parts(row_ptr(self.data, self.pitch, y), self.width) }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &'a [T]> {
+        let array = *self;
+
+        (0..self.height).map(move |y| array.row(y))
+    }
+
+    /// Elements visited by the current thread in a 2D grid-stride loop, with their coordinates.
+    pub fn grid_stride_iter(&self) -> impl Iterator<Item = ((usize, usize), &'a T)> {
+        let array = *self;
+        debug_check_pitch::<T>(self.width, self.pitch);
+
+        Context::grid_stride_range_2d(self.width, self.height).map(move |(x, y)| {
+            let item = unsafe { &*row_ptr(array.data, array.pitch, y).add(x) };
+
+            ((x, y), item)
+        })
+    }
+}
+
+impl<'a, T> Clone for Pitched2d<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Pitched2d<'a, T> {}
+
+#[cfg(target_os = "cuda")]
+impl<'a, T> Index<(usize, usize)> for Pitched2d<'a, T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        match self.get(x, y) {
+            Some(item) => item,
+            None => panic!("Pitched array index is out of bounds"),
+        }
+    }
+}
+
+impl<'a, T> Pitched2dMut<'a, T> {
+    /// # Safety
+    /// `data` has to point to `height` rows of `width` initialized elements, `pitch` bytes apart,
+    /// which are not accessed through any other array or pointer for the lifetime `'a`.
+    pub unsafe fn from_raw_parts(data: *mut T, width: usize, height: usize, pitch: usize) -> Self {
+        check_pitch::<T>(width, pitch);
+
+        Pitched2dMut {
+            data,
+            width,
+            height,
+            pitch,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Distance between rows in bytes.
+    pub fn pitch(&self) -> usize {
+        self.pitch
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl<'a, T> Pitched2dMut<'a, T> {
+    /// Reference to any element of the array.
+    ///
+    /// Panics when the coordinates are out of bounds.
+    ///
+    /// # Safety
+    /// The element must not be written by other threads while the reference is alive.
+    pub unsafe fn at(&self, x: usize, y: usize) -> &T {
+        &*self.item(x, y)
+    }
+
+    /// Mutable reference to any element of the array.
+    ///
+    /// Panics when the coordinates are out of bounds.
+    ///
+    /// # Safety
+    /// The element must not be accessed by other threads while the reference is alive.
+    pub unsafe fn at_mut(&mut self, x: usize, y: usize) -> &mut T {
+        &mut *self.item(x, y)
+    }
+
+    /// Mutable reference to a whole row.
+    ///
+    /// Panics when the row is out of bounds.
+    ///
+    /// # Safety
+    /// The row must not be accessed by other threads while the reference is alive.
+    pub unsafe fn row_mut(&mut self, y: usize) -> &mut [T] {
+        assert!(y < self.height, "Pitched array row is out of bounds");
+        debug_check_pitch::<T>(self.width, self.pitch);
+
+        slice::from_raw_parts_mut(row_ptr(self.data, self.pitch, y) as *mut T, self.width)
+    }
+
+    /// Elements owned by the current thread, together with their coordinates.
+    pub fn for_this_thread(&mut self) -> impl Iterator<Item = ((usize, usize), &mut T)> {
+        let (data, pitch) = (self.data, self.pitch);
+        debug_check_pitch::<T>(self.width, self.pitch);
+
+        Context::grid_stride_range_2d(self.width, self.height).map(move |(x, y)| {
+            let item = unsafe { &mut *(row_ptr(data, pitch, y) as *mut T).add(x) };
+
+            ((x, y), item)
+        })
+    }
+
+    fn item(&self, x: usize, y: usize) -> *mut T {
+        assert!(
+            x < self.width && y < self.height,
+            "Pitched array index is out of bounds"
+        );
+        debug_check_pitch::<T>(self.width, self.pitch);
+
+        unsafe { (row_ptr(self.data, self.pitch, y) as *mut T).add(x) }
+    }
+}
+
+impl<'a, T> Pitched3d<'a, T> {
+    /// # Safety
+    /// `data` has to point to `depth` planes of `height` rows of `width` initialized elements,
+    /// with rows `pitch` bytes apart, which are not written by anyone for the lifetime `'a`.
+    pub unsafe fn from_raw_parts(
+        data: *const T,
+        width: usize,
+        height: usize,
+        depth: usize,
+        pitch: usize,
+    ) -> Self {
+        check_pitch::<T>(width, pitch);
+
+        Pitched3d {
+            data,
+            width,
+            height,
+            depth,
+            pitch,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Distance between rows in bytes.
+    pub fn pitch(&self) -> usize {
+        self.pitch
+    }
+
+    /// Panics when the plane is out of bounds.
+    pub fn plane(&self, z: usize) -> Pitched2d<'a, T> {
+        assert!(z < self.depth, "Pitched array plane is out of bounds");
+
+        Pitched2d {
+            data: unsafe { row_ptr(self.data, self.pitch, z * self.height) },
+            width: self.width,
+            height: self.height,
+            pitch: self.pitch,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl<'a, T> Pitched3d<'a, T> {
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<&'a T> {
+        match z < self.depth {
+            true => self.plane(z).get(x, y),
+            false => None,
+        }
+    }
+
+    /// Rows of all planes, in order.
+    pub fn rows(&self) -> impl Iterator<Item = &'a [T]> {
+        let array = *self;
+
+        (0..self.depth).flat_map(move |z| array.plane(z).rows())
+    }
+
+    /// Elements visited by the current thread in a 3D grid-stride loop, with their coordinates.
+    pub fn grid_stride_iter(&self) -> impl Iterator<Item = ((usize, usize, usize), &'a T)> {
+        let array = *self;
+        debug_check_pitch::<T>(self.width, self.pitch);
+
+        Context::grid_stride_range_3d(self.width, self.height, self.depth).map(move |(x, y, z)| {
+            let row = unsafe { row_ptr(array.data, array.pitch, z * array.height + y) };
+
+            ((x, y, z), unsafe { &*row.add(x) })
+        })
+    }
+}
+
+impl<'a, T> Clone for Pitched3d<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Pitched3d<'a, T> {}
+
+unsafe impl<'a, T> KernelParam for Pitched2d<'a, T> {
+    const LAYOUT: ParamLayout = ParamLayout::of::<Self>("Pitched2d");
+}
+
+unsafe impl<'a, T> KernelParam for Pitched2dMut<'a, T> {
+    const LAYOUT: ParamLayout = ParamLayout::of::<Self>("Pitched2dMut");
+}
+
+unsafe impl<'a, T> KernelParam for Pitched3d<'a, T> {
+    const LAYOUT: ParamLayout = ParamLayout::of::<Self>("Pitched3d");
+}
+
+unsafe impl<'a, T> KernelParam for Pitched3dMut<'a, T> {
+    const LAYOUT: ParamLayout = ParamLayout::of::<Self>("Pitched3dMut");
+}
+
+#[cfg(target_os = "cuda")]
+impl<'a, T> Index<(usize, usize, usize)> for Pitched3d<'a, T> {
+    type Output = T;
+
+    fn index(&self, (x, y, z): (usize, usize, usize)) -> &T {
+        match self.get(x, y, z) {
+            Some(item) => item,
+            None => panic!("Pitched array index is out of bounds"),
+        }
+    }
+}
+
+impl<'a, T> Pitched3dMut<'a, T> {
+    /// # Safety
+    /// `data` has to point to `depth` planes of `height` rows of `width` initialized elements,
+    /// with rows `pitch` bytes apart, which are not accessed through any other array or pointer
+    /// for the lifetime `'a`.
+    pub unsafe fn from_raw_parts(
+        data: *mut T,
+        width: usize,
+        height: usize,
+        depth: usize,
+        pitch: usize,
+    ) -> Self {
+        check_pitch::<T>(width, pitch);
+
+        Pitched3dMut {
+            data,
+            width,
+            height,
+            depth,
+            pitch,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Distance between rows in bytes.
+    pub fn pitch(&self) -> usize {
+        self.pitch
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl<'a, T> Pitched3dMut<'a, T> {
+    /// Reference to any element of the array.
+    ///
+    /// Panics when the coordinates are out of bounds.
+    ///
+    /// # Safety
+    /// The element must not be written by other threads while the reference is alive.
+    pub unsafe fn at(&self, x: usize, y: usize, z: usize) -> &T {
+        &*self.item(x, y, z)
+    }
+
+    /// Mutable reference to any element of the array.
+    ///
+    /// Panics when the coordinates are out of bounds.
+    ///
+    /// # Safety
+    /// The element must not be accessed by other threads while the reference is alive.
+    pub unsafe fn at_mut(&mut self, x: usize, y: usize, z: usize) -> &mut T {
+        &mut *self.item(x, y, z)
+    }
+
+    /// Elements owned by the current thread, together with their coordinates.
+    pub fn for_this_thread(&mut self) -> impl Iterator<Item = ((usize, usize, usize), &mut T)> {
+        let (data, pitch, height) = (self.data, self.pitch, self.height);
+        debug_check_pitch::<T>(self.width, self.pitch);
+
+        Context::grid_stride_range_3d(self.width, self.height, self.depth).map(move |(x, y, z)| {
+            let row = unsafe { row_ptr(data, pitch, z * height + y) as *mut T };
+
+            ((x, y, z), unsafe { &mut *row.add(x) })
+        })
+    }
+
+    fn item(&self, x: usize, y: usize, z: usize) -> *mut T {
+        assert!(
+            x < self.width && y < self.height && z < self.depth,
+            "Pitched array index is out of bounds"
+        );
+        debug_check_pitch::<T>(self.width, self.pitch);
+
+        unsafe { (row_ptr(self.data, self.pitch, z * self.height + y) as *mut T).add(x) }
+    }
+}
@@ -0,0 +1,647 @@
+//! Pointers tagged with the PTX state space they point into.
+//!
+//! Every pointer keeps a generic address inside, so it has the same ABI as a raw pointer
+//! and can be used as a kernel parameter. Loads and stores of primitive types are emitted as
+//! space-specific instructions (e.g. `ld.global` instead of generic `ld`).
+
+#[cfg(target_os = "cuda")]
+use core::arch::asm;
+use core::fmt;
+use core::marker::PhantomData;
+
+use crate::kernel::{KernelParam, ParamLayout};
+
+/// Pointers which can be converted into a generic address,
+/// e.g. to print space pointers with `cuda_printf!("%p", ptr.as_generic_ptr())`.
+pub trait AsGenericPtr {
+    fn as_generic_ptr(&self) -> *const u8;
+}
+
+/// PTX state space.
+pub trait AddressSpace {
+    const NAME: &'static str;
+
+    /// Checks whether a generic address points into the space (`isspacep`).
+    #[cfg(target_os = "cuda")]
+    fn contains(address: *const u8) -> bool;
+
+    /// Converts a generic address into the space-specific one (`cvta.to`).
+    #[cfg(target_os = "cuda")]
+    fn to_space(address: *const u8) -> u64;
+
+    /// Converts a space-specific address into the generic one (`cvta`).
+    #[cfg(target_os = "cuda")]
+    fn to_generic(address: u64) -> *const u8;
+}
+
+/// Primitive types which have space-specific load instructions.
+pub trait SpaceLoad<S: AddressSpace>: Copy {
+    /// # Safety
+    /// `address` has to be a valid, suitably aligned address in the state space.
+    unsafe fn load(address: u64) -> Self;
+}
+
+/// Primitive types which have space-specific store instructions.
+pub trait SpaceStore<S: AddressSpace>: Copy {
+    /// # Safety
+    /// `address` has to be a valid, suitably aligned address in the state space.
+    unsafe fn store(address: u64, value: Self);
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Global {}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Shared {}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Const {}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Local {}
+
+/// Pointer into the state space `S`.
+#[repr(transparent)]
+pub struct SpacePtr<T, S: AddressSpace> {
+    generic: *mut T,
+    _space: PhantomData<S>,
+}
+
+pub type GlobalPtr<T> = SpacePtr<T, Global>;
+pub type SharedPtr<T> = SpacePtr<T, Shared>;
+pub type ConstPtr<T> = SpacePtr<T, Const>;
+pub type LocalPtr<T> = SpacePtr<T, Local>;
+
+#[cfg(target_os = "cuda")]
+#[allow(improper_ctypes)]
+extern "C" {
+    #[link_name = "llvm.nvvm.isspacep.global"]
+    fn nvvm_isspacep_global(address: *const u8) -> bool;
+
+    #[link_name = "llvm.nvvm.isspacep.shared"]
+    fn nvvm_isspacep_shared(address: *const u8) -> bool;
+
+    #[link_name = "llvm.nvvm.isspacep.const"]
+    fn nvvm_isspacep_const(address: *const u8) -> bool;
+
+    #[link_name = "llvm.nvvm.isspacep.local"]
+    fn nvvm_isspacep_local(address: *const u8) -> bool;
+}
+
+macro_rules! impl_address_space {
+    ($($space:ident => $name:literal, $isspacep:ident;)+) => {$(
+        impl AddressSpace for $space {
+            const NAME: &'static str = $name;
+
+            #[cfg(target_os = "cuda")]
+            #[inline(always)]
+            fn contains(address: *const u8) -> bool {
+                unsafe { $isspacep(address) }
+            }
+
+            #[cfg(target_os = "cuda")]
+            #[inline(always)]
+            fn to_space(address: *const u8) -> u64 {
+                let result: u64;
+
+                unsafe {
+                    asm!(
+                        concat!("cvta.to.", $name, ".u64 {}, {};"),
+                        out(reg64) result,
+                        in(reg64) address as u64,
+                    );
+                }
+
+                result
+            }
+
+            #[cfg(target_os = "cuda")]
+            #[inline(always)]
+            fn to_generic(address: u64) -> *const u8 {
+                let result: u64;
+
+                unsafe {
+                    asm!(
+                        concat!("cvta.", $name, ".u64 {}, {};"),
+                        out(reg64) result,
+                        in(reg64) address,
+                    );
+                }
+
+                result as *const u8
+            }
+        }
+    )+};
+}
+
+impl_address_space! {
+    Global => "global", nvvm_isspacep_global;
+    Shared => "shared", nvvm_isspacep_shared;
+    Const => "const", nvvm_isspacep_const;
+    Local => "local", nvvm_isspacep_local;
+}
+
+impl<T, S: AddressSpace> SpacePtr<T, S> {
+    /// Wraps a generic pointer without checking its state space.
+    ///
+    /// # Safety
+    /// The pointer has to point into the state space `S`.
+    #[inline(always)]
+    pub const unsafe fn from_generic_unchecked(generic: *mut T) -> Self {
+        SpacePtr {
+            generic,
+            _space: PhantomData,
+        }
+    }
+
+    /// Wraps a generic pointer, if it points into the state space `S`.
+    #[cfg(target_os = "cuda")]
+    #[inline(always)]
+    pub fn from_generic(generic: *mut T) -> Option<Self> {
+        match S::contains(generic as *const u8) {
+            true => Some(unsafe { Self::from_generic_unchecked(generic) }),
+            false => None,
+        }
+    }
+
+    /// Creates a pointer from a space-specific address.
+    ///
+    /// # Safety
+    /// The address has to be a valid address in the state space `S`.
+    #[cfg(target_os = "cuda")]
+    #[inline(always)]
+    pub unsafe fn from_space_address(address: u64) -> Self {
+        Self::from_generic_unchecked(S::to_generic(address) as *mut T)
+    }
+
+    #[inline(always)]
+    pub fn as_generic(&self) -> *mut T {
+        self.generic
+    }
+
+    /// Address inside the state space `S`.
+    #[cfg(target_os = "cuda")]
+    #[inline(always)]
+    pub fn space_address(&self) -> u64 {
+        S::to_space(self.generic as *const u8)
+    }
+
+    #[inline(always)]
+    pub fn is_null(&self) -> bool {
+        self.generic.is_null()
+    }
+
+    /// # Safety
+    /// Same as for `pointer::add`.
+    #[inline(always)]
+    pub unsafe fn add(self, count: usize) -> Self {
+        Self::from_generic_unchecked(self.generic.add(count))
+    }
+
+    #[inline(always)]
+    pub fn cast<U>(self) -> SpacePtr<U, S> {
+        unsafe { SpacePtr::from_generic_unchecked(self.generic as *mut U) }
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl<T: SpaceLoad<S>, S: AddressSpace> SpacePtr<T, S> {
+    /// Loads the value with a space-specific instruction.
+    ///
+    /// # Safety
+    /// Same as for `pointer::read`.
+    #[inline(always)]
+    pub unsafe fn read(&self) -> T {
+        T::load(self.space_address())
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl<T: SpaceStore<S>, S: AddressSpace> SpacePtr<T, S> {
+    /// Stores the value with a space-specific instruction.
+    ///
+    /// # Safety
+    /// Same as for `pointer::write`.
+    #[inline(always)]
+    pub unsafe fn write(&self, value: T) {
+        T::store(self.space_address(), value)
+    }
+}
+
+impl<T, S: AddressSpace> Clone for SpacePtr<T, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, S: AddressSpace> Copy for SpacePtr<T, S> {}
+
+unsafe impl<T, S: AddressSpace> KernelParam for SpacePtr<T, S> {
+    const LAYOUT: ParamLayout = ParamLayout::of::<Self>("pointer");
+}
+
+impl<T, S: AddressSpace> PartialEq for SpacePtr<T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.generic == other.generic
+    }
+}
+
+impl<T, S: AddressSpace> fmt::Debug for SpacePtr<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}({:p})", S::NAME, self.generic)
+    }
+}
+
+impl<T, S: AddressSpace> AsGenericPtr for SpacePtr<T, S> {
+    fn as_generic_ptr(&self) -> *const u8 {
+        self.generic as *const u8
+    }
+}
+
+impl<T> AsGenericPtr for *const T {
+    fn as_generic_ptr(&self) -> *const u8 {
+        *self as *const u8
+    }
+}
+
+impl<T> AsGenericPtr for *mut T {
+    fn as_generic_ptr(&self) -> *const u8 {
+        *self as *const u8
+    }
+}
+
+#[cfg(target_os = "cuda")]
+macro_rules! impl_space_load {
+    ($space:ident => $name:literal, [$($ty:ty => $ptx:literal, $reg:ident, $repr:ty;)+]) => {$(
+        impl SpaceLoad<$space> for $ty {
+            #[inline(always)]
+            unsafe fn load(address: u64) -> Self {
+                let value: $repr;
+
+                asm!(
+                    concat!("ld.", $name, ".", $ptx, " {}, [{}];"),
+                    out($reg) value,
+                    in(reg64) address,
+                );
+
+                value as $ty
+            }
+        }
+    )+};
+}
+
+#[cfg(target_os = "cuda")]
+macro_rules! impl_space_store {
+    ($space:ident => $name:literal, [$($ty:ty => $ptx:literal, $reg:ident, $repr:ty;)+]) => {$(
+        impl SpaceStore<$space> for $ty {
+            #[inline(always)]
+            unsafe fn store(address: u64, value: Self) {
+                asm!(
+                    concat!("st.", $name, ".", $ptx, " [{}], {};"),
+                    in(reg64) address,
+                    in($reg) value as $repr,
+                );
+            }
+        }
+    )+};
+}
+
+#[cfg(target_os = "cuda")]
+macro_rules! impl_space_access {
+    ($($kind:ident $space:ident => $name:literal;)+) => {$(
+        $kind! {
+            $space => $name, [
+                u8 => "u8", reg16, u16;
+                i8 => "s8", reg16, i16;
+                u16 => "u16", reg16, u16;
+                i16 => "s16", reg16, i16;
+                u32 => "u32", reg32, u32;
+                i32 => "s32", reg32, i32;
+                f32 => "f32", reg32, f32;
+                u64 => "u64", reg64, u64;
+                i64 => "s64", reg64, i64;
+                f64 => "f64", reg64, f64;
+                usize => "u64", reg64, u64;
+                isize => "s64", reg64, i64;
+            ]
+        }
+    )+};
+}
+
+#[cfg(target_os = "cuda")]
+impl_space_access! {
+    impl_space_load Global => "global";
+    impl_space_load Shared => "shared";
+    impl_space_load Const => "const";
+    impl_space_load Local => "local";
+
+    impl_space_store Global => "global";
+    impl_space_store Shared => "shared";
+    impl_space_store Local => "local";
+}
+
+/// Cache operator of a global load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadCache {
+    /// Cache at all levels (`.ca`).
+    All,
+    /// Cache in L2 only (`.cg`).
+    Global,
+    /// Streaming data, likely accessed once (`.cs`).
+    Streaming,
+    /// Last use of the line (`.lu`).
+    LastUse,
+    /// Don't cache and fetch again (`.cv`).
+    Volatile,
+}
+
+/// Cache operator of a global store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreCache {
+    /// Write back all coherent levels (`.wb`).
+    WriteBack,
+    /// Cache in L2 only (`.cg`).
+    Global,
+    /// Streaming data, likely accessed once (`.cs`).
+    Streaming,
+    /// Write through to system memory (`.wt`).
+    WriteThrough,
+}
+
+/// Primitive types with non-coherent and cache-hinted global memory instructions.
+pub trait GlobalAccess: SpaceLoad<Global> + SpaceStore<Global> {
+    /// # Safety
+    /// `address` has to be a valid, suitably aligned global memory address.
+    unsafe fn load_nc(address: u64) -> Self;
+
+    /// # Safety
+    /// `address` has to be a valid, suitably aligned global memory address.
+    unsafe fn load_cached(address: u64, cache: LoadCache) -> Self;
+
+    /// # Safety
+    /// `address` has to be a valid, suitably aligned global memory address.
+    unsafe fn store_cached(address: u64, value: Self, cache: StoreCache);
+}
+
+/// Primitive types which can be loaded and stored as vectors of `N` elements.
+pub trait VectorAccess<const N: usize>: GlobalAccess {
+    /// # Safety
+    /// `address` has to be a valid, suitably aligned global memory address.
+    unsafe fn load_vector(address: u64) -> [Self; N];
+
+    /// # Safety
+    /// `address` has to be a valid, suitably aligned global memory address.
+    unsafe fn store_vector(address: u64, value: [Self; N]);
+}
+
+#[cfg(target_os = "cuda")]
+impl<T: GlobalAccess> GlobalPtr<T> {
+    /// Loads the value through the non-coherent read-only cache (`ld.global.nc`).
+    ///
+    /// # Safety
+    /// Same as for `read`. Additionally, the memory must not be written by anyone
+    /// during the kernel execution.
+    #[inline(always)]
+    pub unsafe fn read_nc(&self) -> T {
+        T::load_nc(self.space_address())
+    }
+
+    /// Loads the value with a cache operator.
+    ///
+    /// # Safety
+    /// Same as for `read`.
+    #[inline(always)]
+    pub unsafe fn read_cached(&self, cache: LoadCache) -> T {
+        T::load_cached(self.space_address(), cache)
+    }
+
+    /// Stores the value with a cache operator.
+    ///
+    /// # Safety
+    /// Same as for `write`.
+    #[inline(always)]
+    pub unsafe fn write_cached(&self, value: T, cache: StoreCache) {
+        T::store_cached(self.space_address(), value, cache)
+    }
+
+    /// Loads `N` consecutive values with a single vector instruction (`ld.global.v2/v4`).
+    ///
+    /// # Safety
+    /// Same as for `read`. The pointer has to be aligned to the size of the whole vector.
+    #[inline(always)]
+    pub unsafe fn read_vector<const N: usize>(&self) -> [T; N]
+    where
+        T: VectorAccess<N>,
+    {
+        self.check_vector_alignment::<N>();
+
+        T::load_vector(self.space_address())
+    }
+
+    /// Stores `N` consecutive values with a single vector instruction (`st.global.v2/v4`).
+    ///
+    /// # Safety
+    /// Same as for `write`. The pointer has to be aligned to the size of the whole vector.
+    #[inline(always)]
+    pub unsafe fn write_vector<const N: usize>(&self, value: [T; N])
+    where
+        T: VectorAccess<N>,
+    {
+        self.check_vector_alignment::<N>();
+
+        T::store_vector(self.space_address(), value)
+    }
+
+    #[inline(always)]
+    fn check_vector_alignment<const N: usize>(&self) {
+        debug_assert!(
+            self.generic as usize % (N * core::mem::size_of::<T>()) == 0,
+            "Vector access has to be aligned to the size of the vector"
+        );
+    }
+}
+
+#[cfg(target_os = "cuda")]
+macro_rules! impl_global_access {
+    ($($ty:ty => $ptx:literal, $reg:ident, $repr:ty;)+) => {$(
+        impl GlobalAccess for $ty {
+            #[inline(always)]
+            unsafe fn load_nc(address: u64) -> Self {
+                let value: $repr;
+
+                asm!(
+                    concat!("ld.global.nc.", $ptx, " {}, [{}];"),
+                    out($reg) value,
+                    in(reg64) address,
+                );
+
+                value as $ty
+            }
+
+            #[inline(always)]
+            unsafe fn load_cached(address: u64, cache: LoadCache) -> Self {
+                let value: $repr;
+
+                match cache {
+                    LoadCache::All => asm!(
+                        concat!("ld.global.ca.", $ptx, " {}, [{}];"),
+                        out($reg) value,
+                        in(reg64) address,
+                    ),
+
+                    LoadCache::Global => asm!(
+                        concat!("ld.global.cg.", $ptx, " {}, [{}];"),
+                        out($reg) value,
+                        in(reg64) address,
+                    ),
+
+                    LoadCache::Streaming => asm!(
+                        concat!("ld.global.cs.", $ptx, " {}, [{}];"),
+                        out($reg) value,
+                        in(reg64) address,
+                    ),
+
+                    LoadCache::LastUse => asm!(
+                        concat!("ld.global.lu.", $ptx, " {}, [{}];"),
+                        out($reg) value,
+                        in(reg64) address,
+                    ),
+
+                    LoadCache::Volatile => asm!(
+                        concat!("ld.global.cv.", $ptx, " {}, [{}];"),
+                        out($reg) value,
+                        in(reg64) address,
+                    ),
+                }
+
+                value as $ty
+            }
+
+            #[inline(always)]
+            unsafe fn store_cached(address: u64, value: Self, cache: StoreCache) {
+                match cache {
+                    StoreCache::WriteBack => asm!(
+                        concat!("st.global.wb.", $ptx, " [{}], {};"),
+                        in(reg64) address,
+                        in($reg) value as $repr,
+                    ),
+
+                    StoreCache::Global => asm!(
+                        concat!("st.global.cg.", $ptx, " [{}], {};"),
+                        in(reg64) address,
+                        in($reg) value as $repr,
+                    ),
+
+                    StoreCache::Streaming => asm!(
+                        concat!("st.global.cs.", $ptx, " [{}], {};"),
+                        in(reg64) address,
+                        in($reg) value as $repr,
+                    ),
+
+                    StoreCache::WriteThrough => asm!(
+                        concat!("st.global.wt.", $ptx, " [{}], {};"),
+                        in(reg64) address,
+                        in($reg) value as $repr,
+                    ),
+                }
+            }
+        }
+    )+};
+}
+
+#[cfg(target_os = "cuda")]
+impl_global_access! {
+    u8 => "u8", reg16, u16;
+    i8 => "s8", reg16, i16;
+    u16 => "u16", reg16, u16;
+    i16 => "s16", reg16, i16;
+    u32 => "u32", reg32, u32;
+    i32 => "s32", reg32, i32;
+    f32 => "f32", reg32, f32;
+    u64 => "u64", reg64, u64;
+    i64 => "s64", reg64, i64;
+    f64 => "f64", reg64, f64;
+    usize => "u64", reg64, u64;
+    isize => "s64", reg64, i64;
+}
+
+#[cfg(target_os = "cuda")]
+macro_rules! impl_vector_access {
+    ($($ty:ty => $ptx:literal, $reg:ident;)+) => {$(
+        impl VectorAccess<2> for $ty {
+            #[inline(always)]
+            unsafe fn load_vector(address: u64) -> [Self; 2] {
+                let (x, y): ($ty, $ty);
+
+                asm!(
+                    concat!("ld.global.v2.", $ptx, " {{{}, {}}}, [{}];"),
+                    out($reg) x,
+                    out($reg) y,
+                    in(reg64) address,
+                );
+
+                [x, y]
+            }
+
+            #[inline(always)]
+            unsafe fn store_vector(address: u64, value: [Self; 2]) {
+                asm!(
+                    concat!("st.global.v2.", $ptx, " [{}], {{{}, {}}};"),
+                    in(reg64) address,
+                    in($reg) value[0],
+                    in($reg) value[1],
+                );
+            }
+        }
+    )+};
+
+    (@v4 $($ty:ty => $ptx:literal, $reg:ident;)+) => {$(
+        impl VectorAccess<4> for $ty {
+            #[inline(always)]
+            unsafe fn load_vector(address: u64) -> [Self; 4] {
+                let (x, y, z, w): ($ty, $ty, $ty, $ty);
+
+                asm!(
+                    concat!("ld.global.v4.", $ptx, " {{{}, {}, {}, {}}}, [{}];"),
+                    out($reg) x,
+                    out($reg) y,
+                    out($reg) z,
+                    out($reg) w,
+                    in(reg64) address,
+                );
+
+                [x, y, z, w]
+            }
+
+            #[inline(always)]
+            unsafe fn store_vector(address: u64, value: [Self; 4]) {
+                asm!(
+                    concat!("st.global.v4.", $ptx, " [{}], {{{}, {}, {}, {}}};"),
+                    in(reg64) address,
+                    in($reg) value[0],
+                    in($reg) value[1],
+                    in($reg) value[2],
+                    in($reg) value[3],
+                );
+            }
+        }
+    )+};
+}
+
+#[cfg(target_os = "cuda")]
+impl_vector_access! {
+    u32 => "u32", reg32;
+    i32 => "s32", reg32;
+    f32 => "f32", reg32;
+    u64 => "u64", reg64;
+    i64 => "s64", reg64;
+    f64 => "f64", reg64;
+}
+
+#[cfg(target_os = "cuda")]
+impl_vector_access! {
+    @v4
+    u32 => "u32", reg32;
+    i32 => "s32", reg32;
+    f32 => "f32", reg32;
+}
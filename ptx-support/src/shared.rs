@@ -0,0 +1,303 @@
+//! Block-wide shared memory.
+//!
+//! Shared memory is declared with the `#[shared]` attribute from `ptx-support-macros`:
+//!
+//! ```ignore
+//! #[shared]
+//! static TILE: SharedArray<f32, 256>;
+//! ```
+//!
+//! The dynamically sized segment, which size is chosen at launch, is available through
+//! `Context::dynamic_shared()` and `Context::dynamic_shared_regions()`.
+//!
+//! Elements of a shared array are never handed out directly. Instead, a block goes through
+//! alternating phases: during a write phase every thread writes the elements it owns,
+//! and a block-wide barrier has to be passed before anyone can read elements written by others.
+
+use core::cell::UnsafeCell;
+use core::mem::{align_of, size_of, MaybeUninit};
+use core::ops::Deref;
+
+use crate::context::{Block, Context};
+use crate::intrinsics::*;
+use crate::thread_group::ThreadGroup;
+
+/// Types which are sound to be placed in shared memory without initialization
+/// and to be accessed by all threads of a block through a shared reference.
+pub unsafe trait SharedMemory {}
+
+/// Handle to a shared memory variable, created by the `#[shared]` attribute.
+pub struct Shared<T: SharedMemory> {
+    locate: fn() -> *mut T,
+}
+
+/// Fixed size array in shared memory.
+#[repr(transparent)]
+pub struct SharedArray<T, const N: usize> {
+    items: UnsafeCell<MaybeUninit<[T; N]>>,
+}
+
+/// Slice of shared memory, accessible through write and read phases.
+#[repr(transparent)]
+pub struct SharedSlice<T> {
+    items: UnsafeCell<[MaybeUninit<T>]>,
+}
+
+/// Write phase of a shared slice.
+///
+/// The thread with block rank `r` owns elements `r`, `r + block size`, `r + 2 * block size`, etc.
+pub struct SharedWriter<'a, T> {
+    slice: &'a SharedSlice<T>,
+    block: Block,
+}
+
+/// Read phase of a shared slice: every element can be read by every thread.
+pub struct SharedReader<'a, T> {
+    slice: &'a SharedSlice<T>,
+    block: Block,
+}
+
+/// Splits the dynamic shared memory segment into typed regions.
+///
+/// Regions are carved sequentially with correct alignment, so every thread of the block
+/// has to request the same regions in the same order to end up with the same layout.
+pub struct DynamicSharedRegions {
+    base: *mut u8,
+    size: usize,
+    offset: usize,
+}
+
+impl Context {
+    /// Whole dynamic shared memory segment as a slice of `T`.
+    ///
+    /// # Safety
+    /// The segment is the same memory for every call, so it can't be used as another type
+    /// or through regions of `dynamic_shared_regions()` while the returned slice is in use.
+    pub unsafe fn dynamic_shared<T: Copy>() -> &'static SharedSlice<T> {
+        Context::dynamic_shared_regions().rest()
+    }
+
+    /// Carve-out helper for splitting the dynamic shared memory segment.
+    ///
+    /// # Safety
+    /// Every call starts carving at the beginning of the segment, so regions of different
+    /// carve-outs alias each other. Regions of another carve-out or `dynamic_shared()`
+    /// can't be used while the carved regions are in use.
+    pub unsafe fn dynamic_shared_regions() -> DynamicSharedRegions {
+        DynamicSharedRegions {
+            base: dynamic_shared_base(),
+            size: dynamic_shared_size(),
+            offset: 0,
+        }
+    }
+}
+
+impl DynamicSharedRegions {
+    /// Carves a slice of `len` elements.
+    ///
+    /// Panics when the rest of the segment is too small.
+    pub fn slice<T: Copy>(&mut self, len: usize) -> &'static SharedSlice<T> {
+        let data = self.carve::<T>(len.checked_mul(size_of::<T>()));
+
+        unsafe { SharedSlice::from_raw_parts(data, len) }
+    }
+
+    /// Carves a shared variable, e.g. temporary storage of block collectives.
+    ///
+    /// Panics when the rest of the segment is too small.
+    pub fn variable<T: SharedMemory>(&mut self) -> &'static T {
+        unsafe { &*self.carve::<T>(Some(size_of::<T>())) }
+    }
+
+    /// Carves everything left as a slice of `T`.
+    pub fn rest<T: Copy>(&mut self) -> &'static SharedSlice<T> {
+        let len = match size_of::<T>() {
+            0 => 0,
+            item_size => self.remaining::<T>() / item_size,
+        };
+
+        self.slice(len)
+    }
+
+    /// Amount of bytes which can still be carved for `T`, after padding for its alignment.
+    pub fn remaining<T>(&self) -> usize {
+        self.size.saturating_sub(self.aligned_offset::<T>())
+    }
+
+    /// Skips padding until the next region is aligned to `alignment`, a power of two.
+    #[cfg_attr(not(target_feature = "sm_80"), allow(dead_code))]
+    pub(crate) fn align_to(&mut self, alignment: usize) {
+        self.offset = self.size.min(self.aligned_offset_to(alignment));
+    }
+
+    fn aligned_offset<T>(&self) -> usize {
+        self.aligned_offset_to(align_of::<T>())
+    }
+
+    fn aligned_offset_to(&self, alignment: usize) -> usize {
+        assert!(
+            alignment <= DYNAMIC_SHARED_ALIGNMENT,
+            "Dynamic shared memory can't be aligned stricter than 16 bytes"
+        );
+
+        (self.offset + alignment - 1) & !(alignment - 1)
+    }
+
+    fn carve<T>(&mut self, size: Option<usize>) -> *mut T {
+        let start = self.aligned_offset::<T>();
+        let end = size.and_then(|size| start.checked_add(size));
+
+        match end {
+            Some(end) if end <= self.size => {
+                self.offset = end;
+
+                unsafe { self.base.add(start) as *mut T }
+            }
+
+            _ => panic!("Dynamic shared memory segment is too small"),
+        }
+    }
+}
+
+impl<T: SharedMemory> Shared<T> {
+    /// Creates a handle from a function returning the generic address of the variable.
+    ///
+    /// # Safety
+    /// `locate` has to return a properly aligned pointer into the shared memory space,
+    /// which is not used by anything else.
+    pub const unsafe fn from_locator(locate: fn() -> *mut T) -> Self {
+        Shared { locate }
+    }
+}
+
+impl<T: SharedMemory> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*(self.locate)() }
+    }
+}
+
+unsafe impl<T: SharedMemory> Sync for Shared<T> {}
+
+unsafe impl<T: Copy, const N: usize> SharedMemory for SharedArray<T, N> {}
+
+impl<T, const N: usize> Deref for SharedArray<T, N> {
+    type Target = SharedSlice<T>;
+
+    fn deref(&self) -> &SharedSlice<T> {
+        unsafe { SharedSlice::from_raw_parts(self.items.get() as *mut T, N) }
+    }
+}
+
+impl<T> SharedSlice<T> {
+    /// # Safety
+    /// The memory has to be located in shared memory and stay valid for the lifetime `'a`.
+    pub(crate) unsafe fn from_raw_parts<'a>(data: *mut T, len: usize) -> &'a Self {
+        &*(core::ptr::slice_from_raw_parts_mut(data as *mut MaybeUninit<T>, len) as *const Self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.get().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: Copy> SharedSlice<T> {
+    /// Starts a write phase.
+    ///
+    /// # Safety
+    /// Every thread of the block has to start the write phase at the same point,
+    /// and no thread may still be reading the slice in an earlier read phase.
+    /// Only one write phase of the slice can be active at a time.
+    pub unsafe fn writer(&self) -> SharedWriter<'_, T> {
+        SharedWriter {
+            slice: self,
+            block: Context::block(),
+        }
+    }
+
+    /// Starts a read phase without a barrier.
+    ///
+    /// # Safety
+    /// Every element has to be written and made visible to the whole block.
+    #[cfg_attr(not(target_feature = "sm_80"), allow(dead_code))]
+    pub(crate) unsafe fn reader(&self) -> SharedReader<'_, T> {
+        SharedReader {
+            slice: self,
+            block: Context::block(),
+        }
+    }
+
+    pub(crate) fn item(&self, index: usize) -> *mut T {
+        assert!(index < self.len(), "Shared slice index is out of bounds");
+
+        unsafe { (self.items.get() as *mut T).add(index) }
+    }
+}
+
+impl<'a, T: Copy> SharedWriter<'a, T> {
+    /// Writes an element owned by the current thread.
+    ///
+    /// Panics when the element belongs to another thread.
+    pub fn write(&mut self, index: usize, value: T) {
+        assert!(
+            index as u64 % self.block.size() == self.block.thread_rank(),
+            "Shared slice element is owned by another thread"
+        );
+
+        unsafe { self.slice.item(index).write(value) };
+    }
+
+    /// Indices of elements owned by the current thread.
+    pub fn owned_indices(&self) -> impl Iterator<Item = usize> {
+        let rank = self.block.thread_rank() as usize;
+        let stride = self.block.size() as usize;
+
+        (rank..self.slice.len()).step_by(stride)
+    }
+
+    /// Waits for all threads of the block to finish writing and starts the read phase.
+    pub fn sync(self) -> SharedReader<'a, T> {
+        self.block.sync();
+
+        SharedReader {
+            slice: self.slice,
+            block: self.block,
+        }
+    }
+}
+
+impl<'a, T: Copy> SharedReader<'a, T> {
+    /// Reads an element.
+    ///
+    /// Panics when the index is out of bounds.
+    ///
+    /// # Safety
+    /// The element has to be written during the previous write phase,
+    /// shared memory is not initialized otherwise.
+    pub unsafe fn read(&self, index: usize) -> T {
+        self.slice.item(index).read()
+    }
+
+    pub fn len(&self) -> usize {
+        self.slice.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slice.is_empty()
+    }
+
+    /// Waits for all threads of the block to finish reading and starts a new write phase.
+    pub fn sync(self) -> SharedWriter<'a, T> {
+        self.block.sync();
+
+        SharedWriter {
+            slice: self.slice,
+            block: self.block,
+        }
+    }
+}
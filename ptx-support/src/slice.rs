@@ -0,0 +1,198 @@
+//! Slices of global memory passed as kernel parameters.
+//!
+//! Both types are `#[repr(C)]` `(pointer, length)` pairs, passed by value as a single
+//! aggregate parameter, i.e. `.param .align 8 .b8 name[16]` in the PTX assembly.
+//!
+//! Elements of a `DeviceSliceMut` are partitioned between all threads of the grid:
+//! the thread with global index `k` owns elements `k`, `k + T`, `k + 2 * T`, etc.,
+//! where `T` is the total amount of threads. Only owned elements are handed out safely.
+
+use core::marker::PhantomData;
+#[cfg(target_os = "cuda")]
+use core::ops::Index;
+
+#[cfg(target_os = "cuda")]
+use crate::context::Context;
+use crate::kernel::{KernelParam, ParamLayout};
+
+/// Read-only slice of device memory.
+#[repr(C)]
+#[derive(Debug)]
+pub struct DeviceSlice<'a, T> {
+    data: *const T,
+    len: usize,
+    _marker: PhantomData<&'a [T]>,
+}
+
+/// Mutable slice of device memory, partitioned between threads of the grid.
+#[repr(C)]
+#[derive(Debug)]
+pub struct DeviceSliceMut<'a, T> {
+    data: *mut T,
+    len: usize,
+    _marker: PhantomData<&'a mut [T]>,
+}
+
+impl<'a, T> DeviceSlice<'a, T> {
+    /// # Safety
+    /// `data` has to point to `len` initialized elements, which are not written
+    /// by anyone for the lifetime `'a`.
+    pub const unsafe fn from_raw_parts(data: *const T, len: usize) -> Self {
+        DeviceSlice {
+            data,
+            len,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_ptr(&self) -> *const T {
+        self.data
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl<'a, T> DeviceSlice<'a, T> {
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        match index < self.len {
+            true => Some(unsafe { &*self.data.add(index) }),
+            false => None,
+        }
+    }
+
+    /// Element at the global index of the current thread, if the slice is long enough.
+    pub fn owned(&self) -> Option<&'a T> {
+        self.get(Context::global_thread_index() as usize)
+    }
+
+    /// Elements owned by the current thread.
+    pub fn grid_stride_iter(&self) -> impl Iterator<Item = &'a T> {
+        let slice = *self;
+
+        Context::grid_stride_range(self.len).map(move |index| unsafe { &*slice.data.add(index) })
+    }
+}
+
+impl<'a, T> Clone for DeviceSlice<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for DeviceSlice<'a, T> {}
+
+unsafe impl<'a, T> KernelParam for DeviceSlice<'a, T> {
+    const LAYOUT: ParamLayout = ParamLayout::of::<Self>("DeviceSlice");
+}
+
+unsafe impl<'a, T> KernelParam for DeviceSliceMut<'a, T> {
+    const LAYOUT: ParamLayout = ParamLayout::of::<Self>("DeviceSliceMut");
+}
+
+#[cfg(target_os = "cuda")]
+impl<'a, T> Index<usize> for DeviceSlice<'a, T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        match self.get(index) {
+            Some(item) => item,
+            None => panic!("Device slice index is out of bounds"),
+        }
+    }
+}
+
+impl<'a, T> DeviceSliceMut<'a, T> {
+    /// # Safety
+    /// `data` has to point to `len` initialized elements, which are not accessed
+    /// through any other slice or pointer for the lifetime `'a`.
+    pub const unsafe fn from_raw_parts(data: *mut T, len: usize) -> Self {
+        DeviceSliceMut {
+            data,
+            len,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_ptr(&self) -> *const T {
+        self.data
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.data
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl<'a, T> DeviceSliceMut<'a, T> {
+    /// Reference to any element of the slice.
+    ///
+    /// Panics when the index is out of bounds.
+    ///
+    /// # Safety
+    /// The element must not be written by other threads while the reference is alive.
+    pub unsafe fn at(&self, index: usize) -> &T {
+        &*self.item(index)
+    }
+
+    /// Mutable reference to any element of the slice.
+    ///
+    /// Panics when the index is out of bounds.
+    ///
+    /// # Safety
+    /// The element must not be accessed by other threads while the reference is alive.
+    pub unsafe fn at_mut(&mut self, index: usize) -> &mut T {
+        &mut *self.item(index)
+    }
+
+    /// Element at the global index of the current thread, if the slice is long enough.
+    ///
+    /// Global indices are unique within a launch, so no other thread can get the same element:
+    ///
+    /// ```ignore
+    /// if let (Some(output), Some(input)) = (output.owned_mut(), input.owned()) {
+    ///     *output = *input * 2.0;
+    /// }
+    /// ```
+    pub fn owned_mut(&mut self) -> Option<&mut T> {
+        let index = Context::global_thread_index() as usize;
+
+        match index < self.len {
+            true => Some(unsafe { &mut *self.data.add(index) }),
+            false => None,
+        }
+    }
+
+    /// Elements owned by the current thread.
+    pub fn grid_stride_iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.for_this_thread().map(|(_, item)| item)
+    }
+
+    /// Elements owned by the current thread, together with their indices.
+    pub fn for_this_thread(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        let data = self.data;
+
+        Context::grid_stride_range(self.len)
+            .map(move |index| (index, unsafe { &mut *data.add(index) }))
+    }
+
+    fn item(&self, index: usize) -> *mut T {
+        assert!(index < self.len, "Device slice index is out of bounds");
+
+        unsafe { self.data.add(index) }
+    }
+}
@@ -0,0 +1,231 @@
+#[cfg(target_os = "cuda")]
+use core::arch::nvptx::*;
+#[cfg(target_os = "cuda")]
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::context::{Block, Context, Grid};
+#[cfg(not(target_os = "cuda"))]
+use crate::emu;
+#[cfg(target_os = "cuda")]
+use crate::intrinsics::*;
+
+pub const WARP_SIZE: u64 = 32;
+
+/// Amount of blocks that can be resident on the device at once.
+///
+/// Grid-wide synchronization only works when every block of the grid is resident,
+/// as cooperative launches guarantee. A launcher can record the limit by writing this symbol
+/// (e.g. through `cuModuleGetGlobal`) before the launch, and debug builds will check it.
+/// Zero means the limit is unknown.
+#[cfg(target_os = "cuda")]
+#[no_mangle]
+pub static PTX_SUPPORT_MAX_CORESIDENT_BLOCKS: AtomicU32 = AtomicU32::new(0);
+
+#[cfg(target_os = "cuda")]
+static GRID_BARRIER_ARRIVED: AtomicU32 = AtomicU32::new(0);
+#[cfg(target_os = "cuda")]
+static GRID_BARRIER_GENERATION: AtomicU32 = AtomicU32::new(0);
+
+/// Set of threads that can cooperate and synchronize with each other.
+pub trait ThreadGroup {
+    /// Amount of threads in the group.
+    fn size(&self) -> u64;
+
+    /// Index of the current thread inside the group, in range `0..size()`.
+    fn thread_rank(&self) -> u64;
+
+    /// Waits until every thread of the group reaches this point.
+    fn sync(&self);
+}
+
+/// Group of `N` consecutive threads of a block, where `N` is a power of two up to the warp size.
+#[cfg(target_os = "cuda")]
+#[derive(Debug)]
+pub struct Tile<const N: usize> {
+    block_rank: u64,
+}
+
+/// Group of threads which were active in the warp at the moment of creation.
+#[cfg(target_os = "cuda")]
+#[derive(Debug)]
+pub struct CoalescedGroup {
+    mask: u32,
+}
+
+/// Splits the block into tiles of `N` threads and returns the one the current thread belongs to.
+#[cfg(target_os = "cuda")]
+pub fn tiled_partition<const N: usize>(parent: &Block) -> Tile<N> {
+    let () = Tile::<N>::SIZE_CHECK;
+
+    Tile {
+        block_rank: parent.thread_rank(),
+    }
+}
+
+/// Creates a group of currently active threads of the warp.
+#[cfg(target_os = "cuda")]
+pub fn coalesced_threads() -> CoalescedGroup {
+    CoalescedGroup {
+        mask: active_mask(),
+    }
+}
+
+impl ThreadGroup for Block {
+    fn size(&self) -> u64 {
+        self.dims().x * self.dims().y * self.dims().z
+    }
+
+    fn thread_rank(&self) -> u64 {
+        let thread = Context::thread();
+
+        (thread.z * self.dims().y + thread.y) * self.dims().x + thread.x
+    }
+
+    #[cfg(target_os = "cuda")]
+    fn sync(&self) {
+        unsafe {
+            _syncthreads();
+        }
+    }
+
+    #[cfg(not(target_os = "cuda"))]
+    fn sync(&self) {
+        emu::unsupported("Block synchronization");
+    }
+}
+
+impl Grid {
+    fn blocks_count(&self) -> u64 {
+        self.dims().x * self.dims().y * self.dims().z
+    }
+
+    fn block_rank(&self) -> u64 {
+        let block = Context::block();
+
+        (block.index().z * self.dims().y + block.index().y) * self.dims().x + block.index().x
+    }
+
+    #[cfg(all(target_os = "cuda", debug_assertions))]
+    fn check_coresidency(&self) {
+        let limit = PTX_SUPPORT_MAX_CORESIDENT_BLOCKS.load(Ordering::Relaxed) as u64;
+
+        if limit != 0 && self.blocks_count() > limit {
+            #[cfg(feature = "macros")]
+            {
+                use crate::prelude::*;
+
+                cuda_printf!(
+                    "Grid-wide sync needs all %lu blocks to be resident, but only %lu can be.\n",
+                    self.blocks_count(),
+                    limit,
+                );
+            }
+
+            panic!("Grid exceeds the co-resident blocks limit of a cooperative launch");
+        }
+    }
+}
+
+/// The whole grid of a cooperative launch.
+impl ThreadGroup for Grid {
+    fn size(&self) -> u64 {
+        self.blocks_count() * Context::block().size()
+    }
+
+    fn thread_rank(&self) -> u64 {
+        let block = Context::block();
+
+        self.block_rank() * block.size() + block.thread_rank()
+    }
+
+    #[cfg(target_os = "cuda")]
+    fn sync(&self) {
+        #[cfg(debug_assertions)]
+        self.check_coresidency();
+
+        let block = Context::block();
+        block.sync();
+
+        if block.thread_rank() == 0 {
+            let generation = GRID_BARRIER_GENERATION.load(Ordering::Acquire);
+            thread_fence();
+
+            if GRID_BARRIER_ARRIVED.fetch_add(1, Ordering::AcqRel) as u64 + 1 == self.blocks_count()
+            {
+                GRID_BARRIER_ARRIVED.store(0, Ordering::Relaxed);
+                GRID_BARRIER_GENERATION.fetch_add(1, Ordering::Release);
+            } else {
+                while GRID_BARRIER_GENERATION.load(Ordering::Acquire) == generation {
+                    core::hint::spin_loop();
+                }
+            }
+
+            thread_fence();
+        }
+
+        block.sync();
+    }
+
+    #[cfg(not(target_os = "cuda"))]
+    fn sync(&self) {
+        emu::unsupported("Grid synchronization");
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl<const N: usize> Tile<N> {
+    const SIZE_CHECK: () = assert!(
+        N.is_power_of_two() && N as u64 <= WARP_SIZE,
+        "Tile size must be a power of two not larger than the warp size"
+    );
+
+    /// Index of the tile inside the block.
+    pub fn meta_group_rank(&self) -> u64 {
+        self.block_rank / N as u64
+    }
+
+    /// Mask of warp lanes belonging to the tile.
+    pub fn mask(&self) -> u32 {
+        let first_lane = (self.block_rank % WARP_SIZE) & !(N as u64 - 1);
+
+        ((((1u64 << N) - 1) << first_lane) & 0xffff_ffff) as u32
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl<const N: usize> ThreadGroup for Tile<N> {
+    fn size(&self) -> u64 {
+        N as u64
+    }
+
+    fn thread_rank(&self) -> u64 {
+        self.block_rank % N as u64
+    }
+
+    fn sync(&self) {
+        warp_sync(self.mask());
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl CoalescedGroup {
+    /// Mask of warp lanes belonging to the group.
+    pub fn mask(&self) -> u32 {
+        self.mask
+    }
+}
+
+#[cfg(target_os = "cuda")]
+impl ThreadGroup for CoalescedGroup {
+    fn size(&self) -> u64 {
+        self.mask.count_ones() as u64
+    }
+
+    fn thread_rank(&self) -> u64 {
+        (self.mask & lanemask_lt()).count_ones() as u64
+    }
+
+    fn sync(&self) {
+        warp_sync(self.mask);
+    }
+}
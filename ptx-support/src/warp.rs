@@ -0,0 +1,391 @@
+//! Warp-wide collectives built on top of shuffles.
+//!
+//! Every function here has to be called by all 32 lanes of the warp.
+
+#[cfg(target_os = "cuda")]
+use crate::intrinsics::*;
+use crate::thread_group::WARP_SIZE;
+
+#[cfg(not(target_os = "cuda"))]
+use self::tests::{lane_id, shfl_idx, shfl_up, shfl_xor};
+
+pub const FULL_MASK: u32 = 0xffff_ffff;
+
+/// Values that can be exchanged between lanes of a warp.
+pub trait Shuffle: Copy {
+    /// Returns the value of lane `lane_id() ^ lane_mask`.
+    fn shfl_xor(self, lane_mask: u32) -> Self;
+
+    /// Returns the value of lane `lane_id() - delta`, or own value for lanes below `delta`.
+    fn shfl_up(self, delta: u32) -> Self;
+
+    /// Returns the value of lane `lane`.
+    fn shfl_idx(self, lane: u32) -> Self;
+}
+
+/// Associative binary operation used by warp collectives.
+pub trait WarpOp<T> {
+    fn combine(&self, a: T, b: T) -> T;
+
+    /// Hardware accelerated reduction, if the target has one for this operation and type.
+    #[inline(always)]
+    fn reduce_fast(&self, _value: T) -> Option<T> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sum;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Min;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Max;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BitAnd;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BitOr;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BitXor;
+
+/// Reduces `value` across the warp. Every lane receives the result.
+#[inline]
+pub fn reduce<T: Shuffle, O: WarpOp<T>>(value: T, op: O) -> T {
+    if let Some(result) = op.reduce_fast(value) {
+        return result;
+    }
+
+    let mut value = value;
+    let mut lane_mask = WARP_SIZE as u32 / 2;
+
+    while lane_mask > 0 {
+        value = op.combine(value, value.shfl_xor(lane_mask));
+        lane_mask /= 2;
+    }
+
+    value
+}
+
+/// Computes an inclusive prefix of `op` across the warp in lane order.
+#[inline]
+pub fn inclusive_scan<T: Shuffle, O: WarpOp<T>>(value: T, op: O) -> T {
+    scan(value, &op)
+}
+
+/// Computes an exclusive prefix of `op` across the warp in lane order, seeded with `initial`.
+///
+/// Lane 0 receives `initial`, lane `i` receives `initial` combined with values of lanes `0..i`.
+#[inline]
+pub fn exclusive_scan<T: Shuffle, O: WarpOp<T>>(value: T, initial: T, op: O) -> T {
+    let preceding = scan(value, &op).shfl_up(1);
+
+    match lane_id() {
+        0 => initial,
+        _ => op.combine(initial, preceding),
+    }
+}
+
+#[inline(always)]
+fn scan<T: Shuffle, O: WarpOp<T>>(value: T, op: &O) -> T {
+    let lane = lane_id();
+
+    let mut value = value;
+    let mut delta = 1;
+
+    while delta < WARP_SIZE as u32 {
+        let other = value.shfl_up(delta);
+
+        if lane >= delta {
+            value = op.combine(other, value);
+        }
+
+        delta *= 2;
+    }
+
+    value
+}
+
+impl<T, F: Fn(T, T) -> T> WarpOp<T> for F {
+    #[inline(always)]
+    fn combine(&self, a: T, b: T) -> T {
+        self(a, b)
+    }
+}
+
+impl Shuffle for u32 {
+    #[inline(always)]
+    fn shfl_xor(self, lane_mask: u32) -> Self {
+        shfl_xor(FULL_MASK, self, lane_mask)
+    }
+
+    #[inline(always)]
+    fn shfl_up(self, delta: u32) -> Self {
+        shfl_up(FULL_MASK, self, delta)
+    }
+
+    #[inline(always)]
+    fn shfl_idx(self, lane: u32) -> Self {
+        shfl_idx(FULL_MASK, self, lane)
+    }
+}
+
+impl Shuffle for u64 {
+    #[inline(always)]
+    fn shfl_xor(self, lane_mask: u32) -> Self {
+        join(
+            split(self).0.shfl_xor(lane_mask),
+            split(self).1.shfl_xor(lane_mask),
+        )
+    }
+
+    #[inline(always)]
+    fn shfl_up(self, delta: u32) -> Self {
+        join(split(self).0.shfl_up(delta), split(self).1.shfl_up(delta))
+    }
+
+    #[inline(always)]
+    fn shfl_idx(self, lane: u32) -> Self {
+        join(split(self).0.shfl_idx(lane), split(self).1.shfl_idx(lane))
+    }
+}
+
+macro_rules! impl_shuffle_via_bits {
+    ($($ty:ty,)+) => {$(
+        impl Shuffle for $ty {
+            #[inline(always)]
+            fn shfl_xor(self, lane_mask: u32) -> Self {
+                <$ty>::from_bits(self.to_bits().shfl_xor(lane_mask))
+            }
+
+            #[inline(always)]
+            fn shfl_up(self, delta: u32) -> Self {
+                <$ty>::from_bits(self.to_bits().shfl_up(delta))
+            }
+
+            #[inline(always)]
+            fn shfl_idx(self, lane: u32) -> Self {
+                <$ty>::from_bits(self.to_bits().shfl_idx(lane))
+            }
+        }
+    )+};
+}
+
+macro_rules! impl_shuffle_via_cast {
+    ($($ty:ty => $bits:ty,)+) => {$(
+        impl Shuffle for $ty {
+            #[inline(always)]
+            fn shfl_xor(self, lane_mask: u32) -> Self {
+                (self as $bits).shfl_xor(lane_mask) as $ty
+            }
+
+            #[inline(always)]
+            fn shfl_up(self, delta: u32) -> Self {
+                (self as $bits).shfl_up(delta) as $ty
+            }
+
+            #[inline(always)]
+            fn shfl_idx(self, lane: u32) -> Self {
+                (self as $bits).shfl_idx(lane) as $ty
+            }
+        }
+    )+};
+}
+
+impl_shuffle_via_bits! {
+    f32,
+    f64,
+}
+
+impl_shuffle_via_cast! {
+    i32 => u32,
+    i64 => u64,
+    usize => u64,
+    isize => u64,
+}
+
+macro_rules! impl_op {
+    ($op:ident, |$a:ident, $b:ident| $combine:expr, [$($ty:ty),+]) => {$(
+        impl WarpOp<$ty> for $op {
+            #[inline(always)]
+            fn combine(&self, $a: $ty, $b: $ty) -> $ty {
+                $combine
+            }
+        }
+    )+};
+}
+
+macro_rules! impl_op_with_redux {
+    ($op:ident, |$a:ident, $b:ident| $combine:expr, $($ty:ty => $redux:ident),+) => {$(
+        impl WarpOp<$ty> for $op {
+            #[inline(always)]
+            fn combine(&self, $a: $ty, $b: $ty) -> $ty {
+                $combine
+            }
+
+            #[cfg(target_feature = "sm_80")]
+            #[inline(always)]
+            fn reduce_fast(&self, value: $ty) -> Option<$ty> {
+                Some(redux::$redux(FULL_MASK, value as u32) as $ty)
+            }
+        }
+    )+};
+}
+
+impl_op!(Sum, |a, b| a.wrapping_add(b), [u64, i64, usize, isize]);
+impl_op!(Sum, |a, b| a + b, [f32, f64]);
+impl_op!(Min, |a, b| a.min(b), [u64, i64, usize, isize, f32, f64]);
+impl_op!(Max, |a, b| a.max(b), [u64, i64, usize, isize, f32, f64]);
+impl_op!(BitAnd, |a, b| a & b, [u64, i64, usize, isize]);
+impl_op!(BitOr, |a, b| a | b, [u64, i64, usize, isize]);
+impl_op!(BitXor, |a, b| a ^ b, [u64, i64, usize, isize]);
+
+impl_op_with_redux!(Sum, |a, b| a.wrapping_add(b), u32 => add, i32 => add);
+impl_op_with_redux!(Min, |a, b| a.min(b), u32 => umin, i32 => min);
+impl_op_with_redux!(Max, |a, b| a.max(b), u32 => umax, i32 => max);
+impl_op_with_redux!(BitAnd, |a, b| a & b, u32 => and, i32 => and);
+impl_op_with_redux!(BitOr, |a, b| a | b, u32 => or, i32 => or);
+impl_op_with_redux!(BitXor, |a, b| a ^ b, u32 => xor, i32 => xor);
+
+#[inline(always)]
+fn split(value: u64) -> (u32, u32) {
+    (value as u32, (value >> 32) as u32)
+}
+
+#[inline(always)]
+fn join(low: u32, high: u32) -> u64 {
+    u64::from(low) | (u64::from(high) << 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::sync::{Arc, Barrier, Mutex};
+    use std::thread;
+    use std::vec::Vec;
+
+    use super::*;
+
+    /// Emulated warp, with a thread for every lane.
+    struct Lanes {
+        values: Mutex<[u32; WARP_SIZE as usize]>,
+        barrier: Barrier,
+    }
+
+    std::thread_local! {
+        static LANE: RefCell<Option<(u32, Arc<Lanes>)>> = const { RefCell::new(None) };
+    }
+
+    /// Runs `kernel` on all lanes of a warp and returns results in lane order.
+    fn run_warp<T: Send, F: Fn(u32) -> T + Sync>(kernel: F) -> Vec<T> {
+        let lanes = Arc::new(Lanes {
+            values: Mutex::new([0; WARP_SIZE as usize]),
+            barrier: Barrier::new(WARP_SIZE as usize),
+        });
+
+        thread::scope(|scope| {
+            let handles = (0..WARP_SIZE as u32)
+                .map(|lane| {
+                    let lanes = lanes.clone();
+                    let kernel = &kernel;
+
+                    scope.spawn(move || {
+                        LANE.with(|current| *current.borrow_mut() = Some((lane, lanes)));
+                        kernel(lane)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+
+    /// Every lane publishes `value` and receives the value of lane `source(lane_id())`.
+    fn exchange(value: u32, source: impl Fn(u32) -> u32) -> u32 {
+        LANE.with(|current| {
+            let current = current.borrow();
+            let (lane, lanes) = current.as_ref().unwrap();
+
+            // Waits until all lanes have read the previous exchange.
+            lanes.barrier.wait();
+            lanes.values.lock().unwrap()[*lane as usize] = value;
+            lanes.barrier.wait();
+
+            let values = lanes.values.lock().unwrap();
+            values[source(*lane) as usize]
+        })
+    }
+
+    pub fn lane_id() -> u32 {
+        LANE.with(|current| current.borrow().as_ref().unwrap().0)
+    }
+
+    pub fn shfl_xor(_mask: u32, value: u32, lane_mask: u32) -> u32 {
+        exchange(value, |lane| lane ^ lane_mask)
+    }
+
+    pub fn shfl_up(_mask: u32, value: u32, delta: u32) -> u32 {
+        exchange(value, |lane| lane.checked_sub(delta).unwrap_or(lane))
+    }
+
+    pub fn shfl_idx(_mask: u32, value: u32, lane: u32) -> u32 {
+        exchange(value, |_| lane)
+    }
+
+    #[test]
+    fn test_reduce() {
+        let results = run_warp(|lane| {
+            (
+                reduce(lane, Sum),
+                reduce(i64::from(lane) - 16, Min),
+                reduce(lane as f32 * 0.5, Max),
+                reduce(1u64 << (lane + 16), BitOr),
+            )
+        });
+
+        for result in results {
+            assert_eq!(result, (496, -16, 15.5, 0x0000_ffff_ffff_0000));
+        }
+    }
+
+    #[test]
+    fn test_inclusive_scan() {
+        let results = run_warp(|lane| {
+            (
+                inclusive_scan(lane, Sum),
+                inclusive_scan(1.0f64, Sum),
+                // Lane order: combining keeps the value of the first lane.
+                inclusive_scan(lane + 100, |first: u32, _| first),
+            )
+        });
+
+        for (lane, result) in results.into_iter().enumerate() {
+            let lane = lane as u32;
+            assert_eq!(result, (lane * (lane + 1) / 2, f64::from(lane + 1), 100));
+        }
+    }
+
+    #[test]
+    fn test_exclusive_scan() {
+        let results = run_warp(|lane| {
+            (
+                exclusive_scan(1u64, 7, Sum),
+                // Every lane except the first one receives the value of the previous lane.
+                exclusive_scan(lane + 100, 0, |_, last: u32| last),
+                exclusive_scan(lane + 100, 5, |first: u32, _| first),
+            )
+        });
+
+        assert_eq!(results[0], (7, 0, 5));
+
+        for (lane, result) in results.into_iter().enumerate().skip(1) {
+            assert_eq!(result, (7 + lane as u64, 99 + lane as u32, 5));
+        }
+    }
+}